@@ -101,7 +101,7 @@ impl CLInfo<cl_device_info> for cl_device_id {
             CL_DEVICE_MAX_CONSTANT_BUFFER_SIZE => cl_prop::<cl_ulong>(dev.const_max_size()),
             CL_DEVICE_MAX_GLOBAL_VARIABLE_SIZE => cl_prop::<usize>(0),
             CL_DEVICE_MAX_MEM_ALLOC_SIZE => cl_prop::<cl_ulong>(dev.max_mem_alloc()),
-            CL_DEVICE_MAX_NUM_SUB_GROUPS => cl_prop::<cl_uint>(0),
+            CL_DEVICE_MAX_NUM_SUB_GROUPS => cl_prop::<cl_uint>(dev.max_subgroups()),
             CL_DEVICE_MAX_ON_DEVICE_EVENTS => cl_prop::<cl_uint>(0),
             CL_DEVICE_MAX_ON_DEVICE_QUEUES => cl_prop::<cl_uint>(0),
             CL_DEVICE_MAX_PARAMETER_SIZE => cl_prop::<usize>(dev.param_max_size()),
@@ -126,13 +126,17 @@ impl CLInfo<cl_device_info> for cl_device_id {
             }
             CL_DEVICE_NAME => cl_prop(dev.screen().name()),
             CL_DEVICE_NATIVE_VECTOR_WIDTH_CHAR => cl_prop::<cl_uint>(1),
-            CL_DEVICE_NATIVE_VECTOR_WIDTH_DOUBLE => cl_prop::<cl_uint>(0),
+            CL_DEVICE_NATIVE_VECTOR_WIDTH_DOUBLE => {
+                cl_prop::<cl_uint>(dev.doubles_supported().into())
+            }
             CL_DEVICE_NATIVE_VECTOR_WIDTH_FLOAT => cl_prop::<cl_uint>(1),
             CL_DEVICE_NATIVE_VECTOR_WIDTH_HALF => cl_prop::<cl_uint>(0),
             CL_DEVICE_NATIVE_VECTOR_WIDTH_INT => cl_prop::<cl_uint>(1),
             CL_DEVICE_NATIVE_VECTOR_WIDTH_LONG => cl_prop::<cl_uint>(1),
             CL_DEVICE_NATIVE_VECTOR_WIDTH_SHORT => cl_prop::<cl_uint>(1),
-            CL_DEVICE_NON_UNIFORM_WORK_GROUP_SUPPORT => cl_prop::<bool>(false),
+            CL_DEVICE_NON_UNIFORM_WORK_GROUP_SUPPORT => {
+                cl_prop::<bool>(dev.non_uniform_work_group_supported())
+            }
             CL_DEVICE_NUMERIC_VERSION => cl_prop::<cl_version>(dev.cl_version as cl_version),
             // TODO subdevice support
             CL_DEVICE_PARENT_DEVICE => cl_prop::<cl_device_id>(cl_device_id::from_ptr(ptr::null())),
@@ -149,7 +153,9 @@ impl CLInfo<cl_device_info> for cl_device_id {
             CL_DEVICE_PREFERRED_LOCAL_ATOMIC_ALIGNMENT => cl_prop::<cl_uint>(0),
             CL_DEVICE_PREFERRED_PLATFORM_ATOMIC_ALIGNMENT => cl_prop::<cl_uint>(0),
             CL_DEVICE_PREFERRED_VECTOR_WIDTH_CHAR => cl_prop::<cl_uint>(1),
-            CL_DEVICE_PREFERRED_VECTOR_WIDTH_DOUBLE => cl_prop::<cl_uint>(0),
+            CL_DEVICE_PREFERRED_VECTOR_WIDTH_DOUBLE => {
+                cl_prop::<cl_uint>(dev.doubles_supported().into())
+            }
             CL_DEVICE_PREFERRED_VECTOR_WIDTH_FLOAT => cl_prop::<cl_uint>(1),
             CL_DEVICE_PREFERRED_VECTOR_WIDTH_HALF => cl_prop::<cl_uint>(0),
             CL_DEVICE_PREFERRED_VECTOR_WIDTH_INT => cl_prop::<cl_uint>(1),
@@ -159,8 +165,8 @@ impl CLInfo<cl_device_info> for cl_device_id {
                 cl_prop::<usize>(dev.subgroups() as usize)
             }
             CL_DEVICE_PRINTF_BUFFER_SIZE => cl_prop::<usize>(dev.printf_buffer_size()),
-            // TODO
-            CL_DEVICE_PROFILING_TIMER_RESOLUTION => cl_prop::<usize>(0),
+            // pipe_screen::get_timestamp returns nanoseconds
+            CL_DEVICE_PROFILING_TIMER_RESOLUTION => cl_prop::<usize>(1),
             CL_DEVICE_OPENCL_C_FEATURES => cl_prop::<&Vec<cl_name_version>>(&dev.clc_features),
             CL_DEVICE_OPENCL_C_VERSION => {
                 cl_prop::<String>(format!("OpenCL C {} ", dev.clc_version.api_str()))
@@ -181,8 +187,10 @@ impl CLInfo<cl_device_info> for cl_device_id {
             CL_DEVICE_SINGLE_FP_CONFIG => cl_prop::<cl_device_fp_config>(
                 (CL_FP_ROUND_TO_NEAREST | CL_FP_INF_NAN) as cl_device_fp_config,
             ),
-            CL_DEVICE_SUB_GROUP_INDEPENDENT_FORWARD_PROGRESS => cl_prop::<bool>(false),
-            CL_DEVICE_SVM_CAPABILITIES => cl_prop::<cl_device_svm_capabilities>(0),
+            CL_DEVICE_SUB_GROUP_INDEPENDENT_FORWARD_PROGRESS => {
+                cl_prop::<bool>(dev.subgroups_independent_forward_progress())
+            }
+            CL_DEVICE_SVM_CAPABILITIES => cl_prop::<cl_device_svm_capabilities>(dev.svm_caps()),
             CL_DEVICE_TYPE => cl_prop::<cl_device_type>(dev.device_type(false)),
             CL_DEVICE_VENDOR => cl_prop(dev.screen().device_vendor()),
             CL_DEVICE_VENDOR_ID => cl_prop::<cl_uint>(dev.vendor_id()),
@@ -215,6 +223,14 @@ fn devs() -> &'static Vec<Arc<Device>> {
 }
 
 pub fn get_devs_for_type(device_type: cl_device_type) -> Vec<&'static Arc<Device>> {
+    // CL_DEVICE_TYPE_ALL: All OpenCL devices available in the platform, except for
+    // CL_DEVICE_TYPE_CUSTOM devices.
+    let device_type = if device_type == CL_DEVICE_TYPE_ALL as cl_device_type {
+        device_type & !(CL_DEVICE_TYPE_CUSTOM as cl_device_type)
+    } else {
+        device_type
+    };
+
     devs()
         .iter()
         .filter(|d| device_type & d.device_type(true) != 0)
@@ -269,18 +285,39 @@ pub fn get_device_ids(
     Ok(())
 }
 
+fn host_time() -> cl_ulong {
+    // this matches CL_PLATFORM_HOST_TIMER_RESOLUTION
+    unsafe { os_time_get_nano() as cl_ulong }
+}
+
 pub fn get_device_and_host_timer(
-    _device: cl_device_id,
-    _device_timestamp: *mut cl_ulong,
-    _host_timestamp: *mut cl_ulong,
+    device: cl_device_id,
+    device_timestamp: *mut cl_ulong,
+    host_timestamp: *mut cl_ulong,
 ) -> CLResult<()> {
-    // TODO: we could support it
-    Err(CL_INVALID_OPERATION)
+    let dev = device.get_ref()?;
+
+    // CL_INVALID_VALUE if host_timestamp or device_timestamp is NULL.
+    if device_timestamp.is_null() || host_timestamp.is_null() {
+        return Err(CL_INVALID_VALUE);
+    }
+
+    device_timestamp.write_checked(dev.screen().get_timestamp());
+    host_timestamp.write_checked(host_time());
+
+    Ok(())
 }
 
-pub fn get_host_timer(_device: cl_device_id, _host_timestamp: *mut cl_ulong) -> CLResult<()> {
-    // TODO: we could support it
-    Err(CL_INVALID_OPERATION)
+pub fn get_host_timer(device: cl_device_id, host_timestamp: *mut cl_ulong) -> CLResult<()> {
+    device.get_ref()?;
+
+    // CL_INVALID_VALUE if host_timestamp is NULL.
+    if host_timestamp.is_null() {
+        return Err(CL_INVALID_VALUE);
+    }
+
+    host_timestamp.write_checked(host_time());
+    Ok(())
 }
 
 pub fn set_default_device_command_queue(
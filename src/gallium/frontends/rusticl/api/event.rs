@@ -43,13 +43,18 @@ impl CLInfo<cl_profiling_info> for cl_event {
             return Err(CL_PROFILING_INFO_NOT_AVAILABLE);
         }
 
+        // CL_PROFILING_INFO_NOT_AVAILABLE if the CL_QUEUE_PROFILING_ENABLE flag is not set for the
+        // command-queue, if the execution status of the command identified by event is not
+        // CL_COMPLETE
+        let times = event.profiling().ok_or(CL_PROFILING_INFO_NOT_AVAILABLE)?;
+
         Ok(match *q {
-            // TODO
-            CL_PROFILING_COMMAND_QUEUED => cl_prop::<cl_ulong>(0),
-            CL_PROFILING_COMMAND_SUBMIT => cl_prop::<cl_ulong>(1),
-            CL_PROFILING_COMMAND_START => cl_prop::<cl_ulong>(2),
-            CL_PROFILING_COMMAND_END => cl_prop::<cl_ulong>(3),
-            CL_PROFILING_COMMAND_COMPLETE => cl_prop::<cl_ulong>(3),
+            CL_PROFILING_COMMAND_QUEUED => cl_prop::<cl_ulong>(times.queued),
+            CL_PROFILING_COMMAND_SUBMIT => cl_prop::<cl_ulong>(times.submit),
+            CL_PROFILING_COMMAND_START => cl_prop::<cl_ulong>(times.start),
+            CL_PROFILING_COMMAND_END => cl_prop::<cl_ulong>(times.end),
+            // we don't support device side enqueue, so this is the same as END
+            CL_PROFILING_COMMAND_COMPLETE => cl_prop::<cl_ulong>(times.end),
             _ => return Err(CL_INVALID_VALUE),
         })
     }
@@ -153,3 +158,18 @@ pub fn create_and_queue(
     }
     Ok(())
 }
+
+// Commands without any work have no observable effect unless the application asked for an event or
+// they have to wait on other events, so don't bother creating and queueing an event in that case.
+pub fn create_and_queue_noop(
+    q: Arc<Queue>,
+    cmd_type: cl_command_type,
+    deps: Vec<Arc<Event>>,
+    event: *mut cl_event,
+) -> CLResult<()> {
+    if event.is_null() && deps.is_empty() {
+        return Ok(());
+    }
+
+    create_and_queue(q, cmd_type, deps, event, false, Box::new(|_, _| Ok(())))
+}
@@ -50,7 +50,7 @@ pub static DISPATCH: cl_icd_dispatch = cl_icd_dispatch {
     clRetainProgram: Some(cl_retain_program),
     clReleaseProgram: Some(cl_release_program),
     clBuildProgram: Some(cl_build_program),
-    clUnloadCompiler: None,
+    clUnloadCompiler: Some(cl_unload_compiler),
     clGetProgramInfo: Some(cl_get_program_info),
     clGetProgramBuildInfo: Some(cl_get_program_build_info),
     clCreateKernel: Some(cl_create_kernel),
@@ -82,7 +82,7 @@ pub static DISPATCH: cl_icd_dispatch = cl_icd_dispatch {
     clEnqueueTask: Some(cl_enqueue_task),
     clEnqueueNativeKernel: None,
     clEnqueueMarker: Some(cl_enqueue_marker),
-    clEnqueueWaitForEvents: None,
+    clEnqueueWaitForEvents: Some(cl_enqueue_wait_for_events),
     clEnqueueBarrier: Some(cl_enqueue_barrier),
     clGetExtensionFunctionAddress: Some(cl_get_extension_function_address),
     clCreateFromGLBuffer: None,
@@ -1258,6 +1258,18 @@ extern "C" fn cl_enqueue_marker(command_queue: cl_command_queue, event: *mut cl_
     match_err!(enqueue_marker(command_queue, event))
 }
 
+extern "C" fn cl_enqueue_wait_for_events(
+    command_queue: cl_command_queue,
+    num_events: cl_uint,
+    event_list: *const cl_event,
+) -> cl_int {
+    match_err!(enqueue_wait_for_events(
+        command_queue,
+        num_events,
+        event_list,
+    ))
+}
+
 extern "C" fn cl_enqueue_barrier(command_queue: cl_command_queue) -> cl_int {
     match_err!(enqueue_barrier(command_queue))
 }
@@ -1485,6 +1497,10 @@ extern "C" fn cl_link_program(
     )
 }
 
+extern "C" fn cl_unload_compiler() -> cl_int {
+    match_err!(unload_compiler())
+}
+
 extern "C" fn cl_unload_platform_compiler(platform: cl_platform_id) -> cl_int {
     match_err!(unload_platform_compiler(platform))
 }
@@ -1647,27 +1663,38 @@ extern "C" fn cl_get_pipe_info(
 }
 
 extern "C" fn cl_svm_alloc(
-    _context: cl_context,
-    _flags: cl_svm_mem_flags,
-    _size: usize,
-    _alignment: ::std::os::raw::c_uint,
+    context: cl_context,
+    flags: cl_svm_mem_flags,
+    size: usize,
+    alignment: ::std::os::raw::c_uint,
 ) -> *mut ::std::os::raw::c_void {
-    ptr::null_mut()
+    svm_alloc(context, flags, size, alignment).unwrap_or(ptr::null_mut())
 }
 
-extern "C" fn cl_svm_free(_context: cl_context, _svm_pointer: *mut ::std::os::raw::c_void) {}
+extern "C" fn cl_svm_free(context: cl_context, svm_pointer: *mut ::std::os::raw::c_void) {
+    svm_free(context, svm_pointer).ok();
+}
 
 extern "C" fn cl_enqueue_svm_free(
-    _command_queue: cl_command_queue,
-    _num_svm_pointers: cl_uint,
-    _svm_pointers: *mut *mut ::std::os::raw::c_void,
-    _pfn_free_func: ::std::option::Option<SVMFreeCb>,
-    _user_data: *mut ::std::os::raw::c_void,
-    _num_events_in_wait_list: cl_uint,
-    _event_wait_list: *const cl_event,
-    _event: *mut cl_event,
+    command_queue: cl_command_queue,
+    num_svm_pointers: cl_uint,
+    svm_pointers: *mut *mut ::std::os::raw::c_void,
+    pfn_free_func: ::std::option::Option<SVMFreeCb>,
+    user_data: *mut ::std::os::raw::c_void,
+    num_events_in_wait_list: cl_uint,
+    event_wait_list: *const cl_event,
+    event: *mut cl_event,
 ) -> cl_int {
-    CL_INVALID_OPERATION
+    match_err!(enqueue_svm_free(
+        command_queue,
+        num_svm_pointers,
+        svm_pointers,
+        pfn_free_func,
+        user_data,
+        num_events_in_wait_list,
+        event_wait_list,
+        event,
+    ))
 }
 
 extern "C" fn cl_enqueue_svm_memcpy(
@@ -1731,20 +1758,25 @@ extern "C" fn cl_create_sampler_with_properties(
 }
 
 extern "C" fn cl_set_kernel_arg_svm_pointer(
-    _kernel: cl_kernel,
-    _arg_index: cl_uint,
-    _arg_value: *const ::std::os::raw::c_void,
+    kernel: cl_kernel,
+    arg_index: cl_uint,
+    arg_value: *const ::std::os::raw::c_void,
 ) -> cl_int {
-    CL_INVALID_OPERATION
+    match_err!(set_kernel_arg_svm_pointer(kernel, arg_index, arg_value))
 }
 
 extern "C" fn cl_set_kernel_exec_info(
-    _kernel: cl_kernel,
-    _param_name: cl_kernel_exec_info,
-    _param_value_size: usize,
-    _param_value: *const ::std::os::raw::c_void,
+    kernel: cl_kernel,
+    param_name: cl_kernel_exec_info,
+    param_value_size: usize,
+    param_value: *const ::std::os::raw::c_void,
 ) -> cl_int {
-    CL_INVALID_OPERATION
+    match_err!(set_kernel_exec_info(
+        kernel,
+        param_name,
+        param_value_size,
+        param_value,
+    ))
 }
 
 extern "C" fn cl_clone_kernel(source_kernel: cl_kernel, errcode_ret: *mut cl_int) -> cl_kernel {
@@ -1761,16 +1793,25 @@ extern "C" fn cl_create_program_with_il(
 }
 
 extern "C" fn cl_enqueue_svm_migrate_mem(
-    _command_queue: cl_command_queue,
-    _num_svm_pointers: cl_uint,
-    _svm_pointers: *mut *const ::std::os::raw::c_void,
-    _sizes: *const usize,
-    _flags: cl_mem_migration_flags,
-    _num_events_in_wait_list: cl_uint,
-    _event_wait_list: *const cl_event,
-    _event: *mut cl_event,
+    command_queue: cl_command_queue,
+    num_svm_pointers: cl_uint,
+    svm_pointers: *mut *const ::std::os::raw::c_void,
+    sizes: *const usize,
+    flags: cl_mem_migration_flags,
+    num_events_in_wait_list: cl_uint,
+    event_wait_list: *const cl_event,
+    event: *mut cl_event,
 ) -> cl_int {
-    CL_INVALID_OPERATION
+    match_err!(enqueue_svm_migrate_mem(
+        command_queue,
+        num_svm_pointers,
+        svm_pointers,
+        sizes,
+        flags,
+        num_events_in_wait_list,
+        event_wait_list,
+        event,
+    ))
 }
 
 extern "C" fn cl_get_device_and_host_timer(
@@ -1,16 +1,20 @@
-use crate::api::event::create_and_queue;
+use crate::api::event::create_and_queue_noop;
 use crate::api::icd::*;
 use crate::api::util::*;
+use crate::core::context::*;
 use crate::core::device::*;
 use crate::core::event::*;
 use crate::core::kernel::*;
+use crate::core::memory::*;
 use crate::core::program::*;
+use crate::core::util::*;
 
 use mesa_rust_util::ptr::*;
 use mesa_rust_util::string::*;
 use rusticl_opencl_gen::*;
 
 use std::collections::HashSet;
+use std::convert::TryInto;
 use std::os::raw::c_void;
 use std::ptr;
 use std::slice;
@@ -85,6 +89,11 @@ impl CLInfoObj<cl_kernel_work_group_info, cl_device_id> for cl_kernel {
             return Err(CL_INVALID_DEVICE);
         }
 
+        // the program might not have been built for all of its devices
+        if !kernel.has_build(&dev) {
+            return Err(CL_INVALID_PROGRAM_EXECUTABLE);
+        }
+
         Ok(match *q {
             CL_KERNEL_COMPILE_WORK_GROUP_SIZE => cl_prop::<[usize; 3]>(kernel.work_group_size),
             CL_KERNEL_LOCAL_MEM_SIZE => cl_prop::<cl_ulong>(kernel.local_mem_size(&dev)),
@@ -104,17 +113,58 @@ impl CLInfoObj<cl_kernel_sub_group_info, (cl_device_id, usize, *const c_void)> f
     fn query(
         &self,
         (d, _input_value_size, _input_value): (cl_device_id, usize, *const c_void),
-        _q: cl_program_build_info,
+        q: cl_kernel_sub_group_info,
     ) -> CLResult<Vec<u8>> {
-        let _kernel = self.get_ref()?;
-        let _dev = d.get_arc()?;
+        let kernel = self.get_ref()?;
+
+        // CL_INVALID_DEVICE [..] if device is NULL but there is more than one device associated
+        // with kernel.
+        let dev = if d.is_null() {
+            if kernel.prog.devs.len() > 1 {
+                return Err(CL_INVALID_DEVICE);
+            } else {
+                kernel.prog.devs[0].clone()
+            }
+        } else {
+            d.get_arc()?
+        };
 
-        Err(CL_INVALID_OPERATION)
+        // CL_INVALID_DEVICE if device is not in the list of devices associated with kernel
+        if !kernel.prog.devs.contains(&dev) {
+            return Err(CL_INVALID_DEVICE);
+        }
+
+        Ok(match q {
+            CL_KERNEL_COMPILE_SUB_GROUP_SIZE_INTEL => cl_prop::<usize>(kernel.subgroup_size),
+            // TODO the remaining queries need real subgroup support
+            _ => return Err(CL_INVALID_OPERATION),
+        })
     }
 }
 
 const ZERO_ARR: [usize; 3] = [0; 3];
 
+/// Returns the size of the element type `type_name` of a pointer kernel argument points to, or
+/// `None` if it's not a builtin scalar or vector type.
+fn pointee_size(type_name: &str) -> Option<usize> {
+    let name = type_name.strip_suffix('*')?.trim();
+    let base = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    let size = match base {
+        "char" | "uchar" => 1,
+        "short" | "ushort" | "half" => 2,
+        "int" | "uint" | "float" => 4,
+        "long" | "ulong" | "double" => 8,
+        _ => return None,
+    };
+    let elems = match &name[base.len()..] {
+        "" => 1,
+        // 3 component vectors are sized like 4 component ones
+        "3" => 4,
+        n => n.parse().ok()?,
+    };
+    Some(size * elems)
+}
+
 /// # Safety
 ///
 /// This function is only safe when called on an array of `work_dim` length
@@ -220,6 +270,128 @@ pub fn create_kernels_in_program(
     Ok(())
 }
 
+fn arg_mem_obj(ctx: &Arc<Context>, mem: cl_mem) -> CLResult<Arc<Mem>> {
+    let mem = mem.get_arc()?;
+
+    // CL_INVALID_MEM_OBJECT for an argument declared to be a memory object when the specified
+    // arg_value is not a valid memory object.
+    //
+    // A memory object of a different context is not valid for this kernel.
+    if &mem.context != ctx {
+        return Err(CL_INVALID_MEM_OBJECT);
+    }
+
+    Ok(mem)
+}
+
+fn arg_sampler(ctx: &Arc<Context>, sampler: cl_sampler) -> CLResult<Arc<Sampler>> {
+    let sampler = sampler.get_arc()?;
+
+    // CL_INVALID_SAMPLER for an argument declared to be of type sampler_t when the specified
+    // arg_value is not a valid sampler object.
+    if &sampler.context != ctx {
+        return Err(CL_INVALID_SAMPLER);
+    }
+
+    Ok(sampler)
+}
+
+// the image type an image argument of type type_name expects
+fn image_type(type_name: &str) -> Option<cl_mem_object_type> {
+    Some(match type_name {
+        "image1d_t" => CL_MEM_OBJECT_IMAGE1D,
+        "image1d_array_t" => CL_MEM_OBJECT_IMAGE1D_ARRAY,
+        "image1d_buffer_t" => CL_MEM_OBJECT_IMAGE1D_BUFFER,
+        "image2d_t" => CL_MEM_OBJECT_IMAGE2D,
+        "image2d_array_t" => CL_MEM_OBJECT_IMAGE2D_ARRAY,
+        "image3d_t" => CL_MEM_OBJECT_IMAGE3D,
+        _ => return None,
+    })
+}
+
+/// Checks a new argument value against the declared argument and prints a warning for values
+/// which are valid, but most likely not what the application intended. Only enabled with
+/// `RUSTICL_DEBUG=args` in debug builds.
+fn audit_arg(k: &Kernel, idx: cl_uint, value: &KernelArgValue) {
+    if !debug_args() {
+        return;
+    }
+
+    let kind = k.args[idx as usize].kind;
+    let type_name = k.arg_type_name(idx);
+    let warn = |msg: &str| {
+        eprintln!(
+            "rusticl: argument {} of type {} of kernel {}: {}",
+            k.arg_name(idx),
+            type_name,
+            k.name,
+            msg
+        )
+    };
+
+    match value {
+        KernelArgValue::Constant(c) => {
+            if let Ok(bytes) = c.as_slice().try_into() {
+                if k.prog.context.is_debug_mem(usize::from_ne_bytes(bytes)) {
+                    warn("a memory object got passed by value");
+                }
+            }
+        }
+        KernelArgValue::MemObject(mem) => {
+            let is_image_arg = [
+                KernelArgType::Image,
+                KernelArgType::RWImage,
+                KernelArgType::Texture,
+            ]
+            .contains(&kind);
+
+            if is_image_arg {
+                if mem.is_buffer() {
+                    warn("buffer bound to an image argument");
+                } else if image_type(type_name).map_or(false, |t| t != mem.mem_type) {
+                    warn(&format!("image of type {:#x} bound", mem.mem_type));
+                }
+                return;
+            }
+
+            if !mem.is_buffer() {
+                warn("image bound to a pointer argument");
+                return;
+            }
+
+            // Kernels writing to CL_MEM_READ_ONLY buffers are undefined, so warn about those being
+            // bound to pointers not declared const.
+            if kind == KernelArgType::MemGlobal
+                && bit_check(mem.flags, CL_MEM_READ_ONLY)
+                && k.type_qualifier(idx) & CL_KERNEL_ARG_TYPE_CONST as cl_kernel_arg_type_qualifier
+                    == 0
+            {
+                warn("CL_MEM_READ_ONLY buffer bound to a non const pointer");
+            }
+
+            // A buffer which can't hold a whole number of elements of the declared type is most
+            // likely the wrong buffer.
+            if let Some(size) = pointee_size(type_name).filter(|&size| mem.size % size != 0) {
+                warn(&format!(
+                    "buffer of size {} isn't a multiple of the element size {}",
+                    mem.size, size
+                ));
+            }
+        }
+        // a size which isn't a multiple of the element size is most likely a byte count the
+        // application got wrong
+        KernelArgValue::LocalMem(arg_size) => {
+            if let Some(size) = pointee_size(type_name).filter(|&size| arg_size % size != 0) {
+                warn(&format!(
+                    "local size {} isn't a multiple of the element size {}",
+                    arg_size, size
+                ));
+            }
+        }
+        _ => {}
+    }
+}
+
 pub fn set_kernel_arg(
     kernel: cl_kernel,
     arg_index: cl_uint,
@@ -285,22 +457,28 @@ pub fn set_kernel_arg(
                         if ptr.is_null() || (*ptr).is_null() {
                             KernelArgValue::None
                         } else {
-                            KernelArgValue::MemObject((*ptr).get_arc()?)
+                            KernelArgValue::MemObject(arg_mem_obj(&k.prog.context, *ptr)?)
                         }
                     }
                     KernelArgType::MemLocal => KernelArgValue::LocalMem(arg_size),
                     KernelArgType::Image | KernelArgType::RWImage | KernelArgType::Texture => {
+                        // images can't be bound if none of the devices supports them
+                        if !k.prog.devs.iter().any(|d| d.image_supported()) {
+                            return Err(CL_INVALID_OPERATION);
+                        }
+
                         let img: *const cl_mem = arg_value.cast();
-                        KernelArgValue::MemObject((*img).get_arc()?)
+                        KernelArgValue::MemObject(arg_mem_obj(&k.prog.context, *img)?)
                     }
                     KernelArgType::Sampler => {
                         let ptr: *const cl_sampler = arg_value.cast();
-                        KernelArgValue::Sampler((*ptr).get_arc()?)
+                        KernelArgValue::Sampler(arg_sampler(&k.prog.context, *ptr)?)
                     }
                 }
             }
         };
-        k.values.get(arg_index as usize).unwrap().replace(Some(arg));
+        audit_arg(&k, arg_index, &arg);
+        k.set_arg(arg_index, arg);
         Ok(())
     } else {
         Err(CL_INVALID_ARG_INDEX)
@@ -311,7 +489,108 @@ pub fn set_kernel_arg(
     //• CL_MAX_SIZE_RESTRICTION_EXCEEDED if the size in bytes of the memory object (if the argument is a memory object) or arg_size (if the argument is declared with local qualifier) exceeds a language- specified maximum size restriction for this argument, such as the MaxByteOffset SPIR-V decoration. This error code is missing before version 2.2.
 }
 
-pub fn enqueue_ndrange_kernel(
+pub fn set_kernel_arg_svm_pointer(
+    kernel: cl_kernel,
+    arg_index: cl_uint,
+    arg_value: *const ::std::os::raw::c_void,
+) -> CLResult<()> {
+    let k = kernel.get_ref()?;
+
+    // CL_INVALID_OPERATION if no devices in the context associated with kernel support SVM.
+    if !k.prog.context.svm_supported() {
+        return Err(CL_INVALID_OPERATION);
+    }
+
+    // CL_INVALID_ARG_INDEX if arg_index is not a valid argument index.
+    let arg = k.args.get(arg_index as usize).ok_or(CL_INVALID_ARG_INDEX)?;
+
+    // SVM pointers can only be passed to arguments declared as a pointer to the global or constant
+    // memory region.
+    if arg.kind != KernelArgType::MemGlobal && arg.kind != KernelArgType::MemConstant {
+        return Err(CL_INVALID_ARG_INDEX);
+    }
+
+    // Since we only support system SVM, the pointer is passed to the kernel as is.
+    let val = if arg.dead {
+        KernelArgValue::None
+    } else {
+        KernelArgValue::SvmPointer(arg_value as usize)
+    };
+
+    k.set_arg(arg_index, val);
+    Ok(())
+
+    // CL_INVALID_ARG_VALUE if arg_value specified is not a valid value.
+}
+
+pub fn set_kernel_exec_info(
+    kernel: cl_kernel,
+    param_name: cl_kernel_exec_info,
+    param_value_size: usize,
+    param_value: *const ::std::os::raw::c_void,
+) -> CLResult<()> {
+    let k = kernel.get_ref()?;
+
+    // CL_INVALID_OPERATION if no devices in the context associated with kernel support SVM.
+    if !k.prog.context.svm_supported() {
+        return Err(CL_INVALID_OPERATION);
+    }
+
+    // CL_INVALID_VALUE if param_name is not valid, if param_value is NULL or if the size specified
+    // by param_value_size is not valid.
+    if param_value.is_null() {
+        return Err(CL_INVALID_VALUE);
+    }
+
+    match param_name {
+        CL_KERNEL_EXEC_INFO_SVM_PTRS => {
+            let ptr_size = std::mem::size_of::<*const c_void>();
+            if param_value_size % ptr_size != 0 {
+                return Err(CL_INVALID_VALUE);
+            }
+
+            let ptrs = unsafe {
+                slice::from_raw_parts(
+                    param_value.cast::<*const c_void>(),
+                    param_value_size / ptr_size,
+                )
+            };
+            k.set_svm_ptrs(ptrs);
+        }
+        CL_KERNEL_EXEC_INFO_SVM_FINE_GRAIN_SYSTEM => {
+            if param_value_size != std::mem::size_of::<cl_bool>() {
+                return Err(CL_INVALID_VALUE);
+            }
+
+            let val = unsafe { *param_value.cast::<cl_bool>() } != CL_FALSE;
+
+            // CL_INVALID_OPERATION if param_name is CL_KERNEL_EXEC_INFO_SVM_FINE_GRAIN_SYSTEM and
+            // param_value is CL_TRUE but no devices in context associated with kernel support
+            // fine-grain system SVM allocations.
+            let devs = &k.prog.context.devs;
+            if val
+                && !devs
+                    .iter()
+                    .any(|d| bit_check(d.svm_caps(), CL_DEVICE_SVM_FINE_GRAIN_SYSTEM))
+            {
+                return Err(CL_INVALID_OPERATION);
+            }
+
+            k.set_fine_grain_system_svm(val);
+        }
+        _ => return Err(CL_INVALID_VALUE),
+    }
+
+    Ok(())
+}
+
+// CL_INVALID_WORK_GROUP_SIZE if the work-group size must be uniform and the local_work_size is not
+// NULL, [...] if the global_work_size is not evenly divisible by the local_work_size.
+fn work_group_fits(non_uniform: bool, gws: usize, lws: usize) -> bool {
+    lws == 0 || non_uniform || gws % lws == 0
+}
+
+fn enqueue_ndrange_kernel_impl(
     command_queue: cl_command_queue,
     kernel: cl_kernel,
     work_dim: cl_uint,
@@ -321,6 +600,7 @@ pub fn enqueue_ndrange_kernel(
     num_events_in_wait_list: cl_uint,
     event_wait_list: *const cl_event,
     event: *mut cl_event,
+    cmd_type: cl_command_type,
 ) -> CLResult<()> {
     let q = command_queue.get_arc()?;
     let k = kernel.get_arc()?;
@@ -338,10 +618,18 @@ pub fn enqueue_ndrange_kernel(
     }
 
     // CL_INVALID_KERNEL_ARGS if the kernel argument values have not been specified.
-    if k.values.iter().any(|v| v.borrow().is_none()) {
+    if !k.all_args_set() {
         return Err(CL_INVALID_KERNEL_ARGS);
     }
 
+    // CL_INVALID_OPERATION if SVM pointers are passed as arguments to a kernel and the device does
+    // not support SVM or if system pointers are passed as arguments to a kernel and/or stored inside
+    // SVM allocations passed as kernel arguments and the device does not support fine grain system
+    // SVM allocations.
+    if k.uses_svm() && !q.device.svm_supported() {
+        return Err(CL_INVALID_OPERATION);
+    }
+
     // CL_INVALID_WORK_DIMENSION if work_dim is not a valid value (i.e. a value between 1 and
     // CL_DEVICE_MAX_WORK_ITEM_DIMENSIONS).
     if work_dim == 0 || work_dim > q.device.max_grid_dimensions() {
@@ -353,6 +641,12 @@ pub fn enqueue_ndrange_kernel(
     let local_work_size = unsafe { kernel_work_arr_or_default(local_work_size, work_dim) };
     let global_work_offset = unsafe { kernel_work_arr_or_default(global_work_offset, work_dim) };
 
+    // a local work size of 0 in some dimensions but not in others can't be a valid work-group,
+    // it's only ok in all dimensions, which we treat like a NULL local_work_size.
+    if local_work_size.contains(&0) && local_work_size.iter().any(|&lws| lws != 0) {
+        return Err(CL_INVALID_WORK_GROUP_SIZE);
+    }
+
     let device_bits = q.device.address_bits();
     let device_max = u64::MAX >> (u64::BITS - device_bits);
 
@@ -369,10 +663,7 @@ pub fn enqueue_ndrange_kernel(
             return Err(CL_INVALID_WORK_ITEM_SIZE);
         }
 
-        // CL_INVALID_WORK_GROUP_SIZE if the work-group size must be uniform and the
-        // local_work_size is not NULL, [...] if the global_work_size is not evenly divisible by
-        // the local_work_size.
-        if lws != 0 && gws % lws != 0 {
+        if !work_group_fits(q.device.non_uniform_work_group_supported(), gws, lws) {
             return Err(CL_INVALID_WORK_GROUP_SIZE);
         }
 
@@ -382,6 +673,12 @@ pub fn enqueue_ndrange_kernel(
             return Err(CL_INVALID_WORK_GROUP_SIZE);
         }
 
+        // if local_work_size is NULL we use the required work-group size, which then has to evenly
+        // divide the global_work_size as well.
+        if lws == 0 && k.work_group_size[i] != 0 && gws % k.work_group_size[i] != 0 {
+            return Err(CL_INVALID_WORK_GROUP_SIZE);
+        }
+
         // CL_INVALID_GLOBAL_WORK_SIZE if any of the values specified in global_work_size[0], …
         // global_work_size[work_dim - 1] exceed the maximum value representable by size_t on
         // the device on which the kernel-instance will be enqueued.
@@ -404,19 +701,21 @@ pub fn enqueue_ndrange_kernel(
     // If global_work_size is NULL, or the value in any passed dimension is 0 then the kernel
     // command will trivially succeed after its event dependencies are satisfied and subsequently
     // update its completion event.
-    let cb: EventSig = if global_work_size.contains(&0) {
-        Box::new(|_, _| Ok(()))
-    } else {
-        k.launch(
-            &q,
-            work_dim,
-            local_work_size,
-            global_work_size,
-            global_work_offset,
-        )?
-    };
+    if global_work_size.contains(&0) {
+        return create_and_queue_noop(q, cmd_type, evs, event);
+    }
 
-    create_and_queue(q, CL_COMMAND_NDRANGE_KERNEL, evs, event, false, cb)
+    let e = q.enqueue_ndrange(
+        &k,
+        cmd_type,
+        work_dim,
+        local_work_size,
+        global_work_size,
+        global_work_offset,
+        evs,
+    )?;
+    cl_event::leak_ref(event, &e);
+    Ok(())
 
     //• CL_INVALID_WORK_GROUP_SIZE if local_work_size is specified and is not consistent with the required number of sub-groups for kernel in the program source.
     //• CL_INVALID_WORK_GROUP_SIZE if local_work_size is specified and the total number of work-items in the work-group computed as local_work_size[0] × … local_work_size[work_dim - 1] is greater than the value specified by CL_KERNEL_WORK_GROUP_SIZE in the Kernel Object Device Queries table.
@@ -428,6 +727,31 @@ pub fn enqueue_ndrange_kernel(
     //• CL_INVALID_OPERATION if SVM pointers are passed as arguments to a kernel and the device does not support SVM or if system pointers are passed as arguments to a kernel and/or stored inside SVM allocations passed as kernel arguments and the device does not support fine grain system SVM allocations.
 }
 
+pub fn enqueue_ndrange_kernel(
+    command_queue: cl_command_queue,
+    kernel: cl_kernel,
+    work_dim: cl_uint,
+    global_work_offset: *const usize,
+    global_work_size: *const usize,
+    local_work_size: *const usize,
+    num_events_in_wait_list: cl_uint,
+    event_wait_list: *const cl_event,
+    event: *mut cl_event,
+) -> CLResult<()> {
+    enqueue_ndrange_kernel_impl(
+        command_queue,
+        kernel,
+        work_dim,
+        global_work_offset,
+        global_work_size,
+        local_work_size,
+        num_events_in_wait_list,
+        event_wait_list,
+        event,
+        CL_COMMAND_NDRANGE_KERNEL,
+    )
+}
+
 pub fn enqueue_task(
     command_queue: cl_command_queue,
     kernel: cl_kernel,
@@ -435,10 +759,26 @@ pub fn enqueue_task(
     event_wait_list: *const cl_event,
     event: *mut cl_event,
 ) -> CLResult<()> {
+    let k = kernel.get_ref()?;
+
+    // CL_INVALID_WORK_GROUP_SIZE if a work-group size is specified for kernel using the
+    // __attribute__((reqd_work_group_size(X, Y, Z))) qualifier in program source and is not
+    // (1, 1, 1).
+    if k.work_group_size != [0; 3] && k.work_group_size != [1; 3] {
+        if debug_launch() {
+            let [x, y, z] = k.work_group_size;
+            eprintln!(
+                "rusticl: clEnqueueTask requires a work-group size of 1x1x1 but kernel {} requires {}x{}x{}",
+                k.name, x, y, z
+            );
+        }
+        return Err(CL_INVALID_WORK_GROUP_SIZE);
+    }
+
     // clEnqueueTask is equivalent to calling clEnqueueNDRangeKernel with work_dim set to 1,
     // global_work_offset set to NULL, global_work_size[0] set to 1, and local_work_size[0] set to
-    // 1.
-    enqueue_ndrange_kernel(
+    // 1. The event reports CL_COMMAND_TASK though.
+    enqueue_ndrange_kernel_impl(
         command_queue,
         kernel,
         1,
@@ -448,6 +788,7 @@ pub fn enqueue_task(
         num_events_in_wait_list,
         event_wait_list,
         event,
+        CL_COMMAND_TASK,
     )
 }
 
@@ -455,3 +796,43 @@ pub fn clone_kernel(source_kernel: cl_kernel) -> CLResult<cl_kernel> {
     let k = source_kernel.get_ref()?;
     Ok(cl_kernel::from_arc(Arc::new(k.clone())))
 }
+
+#[test]
+fn test_arg_from_other_context() {
+    let ctx = Context::new(Vec::new(), Default::default());
+    let other = Context::new(Vec::new(), Default::default());
+
+    let mem = Mem::new_buffer(
+        other.clone(),
+        CL_MEM_READ_WRITE.into(),
+        4,
+        ptr::null_mut(),
+        Vec::new(),
+    )
+    .unwrap();
+    let mem = cl_mem::from_arc(mem);
+    assert_eq!(arg_mem_obj(&ctx, mem).err(), Some(CL_INVALID_MEM_OBJECT));
+    assert!(arg_mem_obj(&other, mem).is_ok());
+
+    let sampler = Sampler::new(
+        other.clone(),
+        false,
+        CL_ADDRESS_NONE,
+        CL_FILTER_NEAREST,
+        None,
+    );
+    let sampler = cl_sampler::from_arc(sampler);
+    assert_eq!(arg_sampler(&ctx, sampler).err(), Some(CL_INVALID_SAMPLER));
+    assert!(arg_sampler(&other, sampler).is_ok());
+}
+
+#[test]
+fn test_non_uniform_work_groups() {
+    assert!(work_group_fits(false, 12, 4));
+    assert!(!work_group_fits(false, 10, 4));
+    // a NULL local_work_size is always fine
+    assert!(work_group_fits(false, 10, 0));
+
+    // devices reporting CL_DEVICE_NON_UNIFORM_WORK_GROUP_SUPPORT accept the remainder
+    assert!(work_group_fits(true, 10, 4));
+}
@@ -1,6 +1,7 @@
 #![allow(non_upper_case_globals)]
 
 use crate::api::event::create_and_queue;
+use crate::api::event::create_and_queue_noop;
 use crate::api::icd::*;
 use crate::api::types::*;
 use crate::api::util::*;
@@ -13,6 +14,9 @@ use mesa_rust_util::properties::Properties;
 use mesa_rust_util::ptr::*;
 use rusticl_opencl_gen::*;
 
+use std::alloc;
+use std::alloc::Layout;
+use std::cmp;
 use std::cmp::Ordering;
 use std::os::raw::c_void;
 use std::ptr;
@@ -223,7 +227,9 @@ impl CLInfo<cl_mem_info> for cl_mem {
             CL_MEM_REFERENCE_COUNT => cl_prop::<cl_uint>(self.refcnt()?),
             CL_MEM_SIZE => cl_prop::<usize>(mem.size),
             CL_MEM_TYPE => cl_prop::<cl_mem_object_type>(mem.mem_type),
-            CL_MEM_USES_SVM_POINTER => cl_prop::<cl_bool>(CL_FALSE),
+            CL_MEM_USES_SVM_POINTER => {
+                cl_prop::<bool>(mem.context.find_svm_alloc(mem.host_ptr).is_some())
+            }
             _ => return Err(CL_INVALID_VALUE),
         })
     }
@@ -681,10 +687,9 @@ pub fn create_image_with_properties(
 
     // CL_INVALID_OPERATION if there are no devices in context that support images (i.e.
     // CL_DEVICE_IMAGE_SUPPORT specified in the Device Queries table is CL_FALSE).
-    c.devs
-        .iter()
-        .find(|d| d.image_supported())
-        .ok_or(CL_INVALID_OPERATION)?;
+    if !c.image_supported() {
+        return Err(CL_INVALID_OPERATION);
+    }
 
     let (format, elem_size) = validate_image_format(image_format)?;
     let (desc, parent) = validate_image_desc(image_desc, host_ptr, elem_size.into(), &c.devs)?;
@@ -819,12 +824,20 @@ pub fn get_supported_image_formats(
 
     let mut res = Vec::<cl_image_format>::new();
     let filtered_flags = filter_image_access_flags(flags);
-    for dev in &c.devs {
-        for f in &dev.formats {
-            let s = f.1.get(&image_type).unwrap_or(&0);
+    let supported = |dev: &Device, f: &cl_image_format| {
+        let s = dev
+            .formats
+            .get(f)
+            .and_then(|f| f.get(&image_type))
+            .unwrap_or(&0);
+        filtered_flags & s == filtered_flags
+    };
 
-            if filtered_flags & s == filtered_flags {
-                res.push(*f.0);
+    // only report formats which can be used on all devices in the context
+    for dev in &c.devs {
+        for f in dev.formats.keys() {
+            if c.devs.iter().all(|d| supported(d, f)) {
+                res.push(*f);
             }
         }
     }
@@ -833,7 +846,9 @@ pub fn get_supported_image_formats(
     res.dedup();
 
     num_image_formats.write_checked(res.len() as cl_uint);
-    unsafe { image_formats.copy_checked(res.as_ptr(), res.len()) };
+    // The number of entries returned is the minimum of num_entries and the actual number of
+    // supported image formats.
+    unsafe { image_formats.copy_checked(res.as_ptr(), cmp::min(num_entries as usize, res.len())) };
 
     Ok(())
 }
@@ -871,10 +886,9 @@ fn create_sampler_impl(
 
     // CL_INVALID_OPERATION if images are not supported by any device associated with context (i.e.
     // CL_DEVICE_IMAGE_SUPPORT specified in the Device Queries table is CL_FALSE).
-    c.devs
-        .iter()
-        .find(|d| d.image_supported())
-        .ok_or(CL_INVALID_OPERATION)?;
+    if !c.image_supported() {
+        return Err(CL_INVALID_OPERATION);
+    }
 
     // CL_INVALID_VALUE if addressing_mode, filter_mode, normalized_coords or a combination of these
     // arguements are not valid.
@@ -1046,6 +1060,19 @@ pub fn enqueue_write_buffer(
     // CL_MISALIGNED_SUB_BUFFER_OFFSET if buffer is a sub-buffer object and offset specified when the sub-buffer object is created is not aligned to CL_DEVICE_MEM_BASE_ADDR_ALIGN value for device associated with queue.
 }
 
+// whether the copied regions overlap in the parent buffer of src and dst
+fn copy_overlaps(src: &Mem, src_offset: usize, dst: &Mem, dst_offset: usize, size: usize) -> bool {
+    if !src.has_same_parent(dst) {
+        return false;
+    }
+
+    let src_offset = src_offset + src.offset;
+    let dst_offset = dst_offset + dst.offset;
+
+    (src_offset <= dst_offset && dst_offset < src_offset + size)
+        || (dst_offset <= src_offset && src_offset < dst_offset + size)
+}
+
 pub fn enqueue_copy_buffer(
     command_queue: cl_command_queue,
     src_buffer: cl_mem,
@@ -1078,15 +1105,8 @@ pub fn enqueue_copy_buffer(
     // and the source and destination regions overlap or if src_buffer and dst_buffer are different
     // sub-buffers of the same associated buffer object and they overlap. The regions overlap if
     // src_offset ≤ dst_offset ≤ src_offset + size - 1 or if dst_offset ≤ src_offset ≤ dst_offset + size - 1.
-    if src.has_same_parent(&dst) {
-        let src_offset = src_offset + src.offset;
-        let dst_offset = dst_offset + dst.offset;
-
-        if (src_offset <= dst_offset && dst_offset < src_offset + size)
-            || (dst_offset <= src_offset && src_offset < dst_offset + size)
-        {
-            return Err(CL_MEM_COPY_OVERLAP);
-        }
+    if copy_overlaps(&src, src_offset, &dst, dst_offset, size) {
+        return Err(CL_MEM_COPY_OVERLAP);
     }
 
     create_and_queue(
@@ -1635,6 +1655,12 @@ pub fn enqueue_read_image(
         return Err(CL_INVALID_CONTEXT);
     }
 
+    // CL_INVALID_OPERATION if the device associated with command_queue does not support images
+    // (i.e. CL_DEVICE_IMAGE_SUPPORT specified in the Device Queries table is CL_FALSE).
+    if !q.device.image_supported() {
+        return Err(CL_INVALID_OPERATION);
+    }
+
     // CL_INVALID_OPERATION if clEnqueueReadImage is called on image which has been created with
     // CL_MEM_HOST_WRITE_ONLY or CL_MEM_HOST_NO_ACCESS.
     if bit_check(i.flags, CL_MEM_HOST_WRITE_ONLY | CL_MEM_HOST_NO_ACCESS) {
@@ -1696,7 +1722,6 @@ pub fn enqueue_read_image(
     //• CL_INVALID_VALUE if values in origin and region do not follow rules described in the argument description for origin and region.
     //• CL_INVALID_IMAGE_SIZE if image dimensions (image width, height, specified or compute row and/or slice pitch) for image are not supported by device associated with queue.
     //• CL_IMAGE_FORMAT_NOT_SUPPORTED if image format (image channel order and data type) for image are not supported by device associated with queue.
-    //• CL_INVALID_OPERATION if the device associated with command_queue does not support images (i.e. CL_DEVICE_IMAGE_SUPPORT specified in the Device Queries table is CL_FALSE).
     //• CL_EXEC_STATUS_ERROR_FOR_EVENTS_IN_WAIT_LIST if the read and write operations are blocking and the execution status of any of the events in event_wait_list is a negative integer value.
 }
 
@@ -1724,6 +1749,12 @@ pub fn enqueue_write_image(
         return Err(CL_INVALID_CONTEXT);
     }
 
+    // CL_INVALID_OPERATION if the device associated with command_queue does not support images
+    // (i.e. CL_DEVICE_IMAGE_SUPPORT specified in the Device Queries table is CL_FALSE).
+    if !q.device.image_supported() {
+        return Err(CL_INVALID_OPERATION);
+    }
+
     // CL_INVALID_OPERATION if clEnqueueWriteImage is called on image which has been created with
     // CL_MEM_HOST_READ_ONLY or CL_MEM_HOST_NO_ACCESS.
     if bit_check(i.flags, CL_MEM_HOST_READ_ONLY | CL_MEM_HOST_NO_ACCESS) {
@@ -1762,7 +1793,7 @@ pub fn enqueue_write_image(
 
     create_and_queue(
         q,
-        CL_COMMAND_WRITE_BUFFER_RECT,
+        CL_COMMAND_WRITE_IMAGE,
         evs,
         event,
         block,
@@ -1785,7 +1816,6 @@ pub fn enqueue_write_image(
     //• CL_INVALID_VALUE if values in origin and region do not follow rules described in the argument description for origin and region.
     //• CL_INVALID_IMAGE_SIZE if image dimensions (image width, height, specified or compute row and/or slice pitch) for image are not supported by device associated with queue.
     //• CL_IMAGE_FORMAT_NOT_SUPPORTED if image format (image channel order and data type) for image are not supported by device associated with queue.
-    //• CL_INVALID_OPERATION if the device associated with command_queue does not support images (i.e. CL_DEVICE_IMAGE_SUPPORT specified in the Device Queries table is CL_FALSE).
     //• CL_EXEC_STATUS_ERROR_FOR_EVENTS_IN_WAIT_LIST if the read and write operations are blocking and the execution status of any of the events in event_wait_list is a negative integer value.
 }
 
@@ -1810,6 +1840,12 @@ pub fn enqueue_copy_image(
         return Err(CL_INVALID_CONTEXT);
     }
 
+    // CL_INVALID_OPERATION if the device associated with command_queue does not support images
+    // (i.e. CL_DEVICE_IMAGE_SUPPORT specified in the Device Queries table is CL_FALSE).
+    if !q.device.image_supported() {
+        return Err(CL_INVALID_OPERATION);
+    }
+
     // CL_IMAGE_FORMAT_MISMATCH if src_image and dst_image do not use the same image format.
     if src_image.image_format != dst_image.image_format {
         return Err(CL_IMAGE_FORMAT_MISMATCH);
@@ -1846,7 +1882,6 @@ pub fn enqueue_copy_image(
     //• CL_INVALID_VALUE if values in src_origin, dst_origin and region do not follow rules described in the argument description for src_origin, dst_origin and region.
     //• CL_INVALID_IMAGE_SIZE if image dimensions (image width, height, specified or compute row and/or slice pitch) for src_image or dst_image are not supported by device associated with queue.
     //• CL_IMAGE_FORMAT_NOT_SUPPORTED if image format (image channel order and data type) for src_image or dst_image are not supported by device associated with queue.
-    //• CL_INVALID_OPERATION if the device associated with command_queue does not support images (i.e. CL_DEVICE_IMAGE_SUPPORT specified in the Device Queries table is CL_FALSE).
     //• CL_MEM_COPY_OVERLAP if src_image and dst_image are the same image object and the source and destination regions overlap.
 }
 
@@ -1869,6 +1904,12 @@ pub fn enqueue_fill_image(
         return Err(CL_INVALID_CONTEXT);
     }
 
+    // CL_INVALID_OPERATION if the device associated with command_queue does not support images
+    // (i.e. CL_DEVICE_IMAGE_SUPPORT specified in the Device Queries table is CL_FALSE).
+    if !q.device.image_supported() {
+        return Err(CL_INVALID_OPERATION);
+    }
+
     // CL_INVALID_VALUE if fill_color is NULL.
     // CL_INVALID_VALUE if origin or region is NULL.
     if fill_color.is_null() || origin.is_null() || region.is_null() {
@@ -1887,7 +1928,7 @@ pub fn enqueue_fill_image(
     let fill_color = unsafe { slice::from_raw_parts(fill_color.cast(), 4).to_vec() };
     create_and_queue(
         q,
-        CL_COMMAND_FILL_BUFFER,
+        CL_COMMAND_FILL_IMAGE,
         evs,
         event,
         false,
@@ -1922,6 +1963,12 @@ pub fn enqueue_copy_buffer_to_image(
         return Err(CL_INVALID_CONTEXT);
     }
 
+    // CL_INVALID_OPERATION if the device associated with command_queue does not support images
+    // (i.e. CL_DEVICE_IMAGE_SUPPORT specified in the Device Queries table is CL_FALSE).
+    if !q.device.image_supported() {
+        return Err(CL_INVALID_OPERATION);
+    }
+
     // CL_INVALID_VALUE if dst_origin or region is NULL.
     if dst_origin.is_null() || region.is_null() {
         return Err(CL_INVALID_VALUE);
@@ -1947,7 +1994,6 @@ pub fn enqueue_copy_buffer_to_image(
     //• CL_INVALID_IMAGE_SIZE if image dimensions (image width, height, specified or compute row and/or slice pitch) for dst_image are not supported by device associated with queue.
     //• CL_IMAGE_FORMAT_NOT_SUPPORTED if image format (image channel order and data type) for dst_image are not supported by device associated with queue.
     //• CL_MEM_OBJECT_ALLOCATION_FAILURE if there is a failure to allocate memory for data store associated with src_buffer or dst_image.
-    //• CL_INVALID_OPERATION if the device associated with command_queue does not support images (i.e. CL_DEVICE_IMAGE_SUPPORT specified in the Device Queries table is CL_FALSE).
 }
 
 pub fn enqueue_copy_image_to_buffer(
@@ -1972,6 +2018,12 @@ pub fn enqueue_copy_image_to_buffer(
         return Err(CL_INVALID_CONTEXT);
     }
 
+    // CL_INVALID_OPERATION if the device associated with command_queue does not support images
+    // (i.e. CL_DEVICE_IMAGE_SUPPORT specified in the Device Queries table is CL_FALSE).
+    if !q.device.image_supported() {
+        return Err(CL_INVALID_OPERATION);
+    }
+
     // CL_INVALID_VALUE if src_origin or region is NULL.
     if src_origin.is_null() || region.is_null() {
         return Err(CL_INVALID_VALUE);
@@ -1997,7 +2049,6 @@ pub fn enqueue_copy_image_to_buffer(
     //• CL_INVALID_IMAGE_SIZE if image dimensions (image width, height, specified or compute row and/or slice pitch) for src_image are not supported by device associated with queue.
     //• CL_IMAGE_FORMAT_NOT_SUPPORTED if image format (image channel order and data type) for src_image are not supported by device associated with queue.
     //• CL_MEM_OBJECT_ALLOCATION_FAILURE if there is a failure to allocate memory for data store associated with src_image or dst_buffer.
-    //• CL_INVALID_OPERATION if the device associated with command_queue does not support images (i.e. CL_DEVICE_IMAGE_SUPPORT specified in the Device Queries table is CL_FALSE).
 }
 
 pub fn enqueue_map_image(
@@ -2026,6 +2077,12 @@ pub fn enqueue_map_image(
         return Err(CL_INVALID_CONTEXT);
     }
 
+    // CL_INVALID_OPERATION if the device associated with command_queue does not support images
+    // (i.e. CL_DEVICE_IMAGE_SUPPORT specified in the Device Queries table is CL_FALSE).
+    if !q.device.image_supported() {
+        return Err(CL_INVALID_OPERATION);
+    }
+
     // CL_INVALID_VALUE if origin or region is NULL.
     // CL_INVALID_VALUE if image_row_pitch is NULL.
     if origin.is_null() || region.is_null() || image_row_pitch.is_null() {
@@ -2074,7 +2131,6 @@ pub fn enqueue_map_image(
     //• CL_IMAGE_FORMAT_NOT_SUPPORTED if image format (image channel order and data type) for image are not supported by device associated with queue.
     //• CL_MAP_FAILURE if there is a failure to map the requested region into the host address space. This error cannot occur for image objects created with CL_MEM_USE_HOST_PTR or CL_MEM_ALLOC_HOST_PTR.
     //• CL_EXEC_STATUS_ERROR_FOR_EVENTS_IN_WAIT_LIST if the map operation is blocking and the execution status of any of the events in event_wait_list is a negative integer value.
-    //• CL_INVALID_OPERATION if the device associated with command_queue does not support images (i.e. CL_DEVICE_IMAGE_SUPPORT specified in the Device Queries table is CL_FALSE).
     //• CL_INVALID_OPERATION if mapping would lead to overlapping regions being mapped for writing.
 }
 
@@ -2136,26 +2192,231 @@ pub fn enqueue_migrate_mem_objects(
     }
 
     // CL_INVALID_VALUE if flags is not 0 or is not any of the values described in the table above.
-    if flags != 0
-        && !bit_check(
-            flags,
-            CL_MIGRATE_MEM_OBJECT_HOST | CL_MIGRATE_MEM_OBJECT_CONTENT_UNDEFINED,
-        )
-    {
+    let valid_flags = (CL_MIGRATE_MEM_OBJECT_HOST | CL_MIGRATE_MEM_OBJECT_CONTENT_UNDEFINED)
+        as cl_mem_migration_flags;
+    if flags & !valid_flags != 0 {
         return Err(CL_INVALID_VALUE);
     }
 
     // we should do something, but it's legal to not do anything at all
+    create_and_queue_noop(q, CL_COMMAND_MIGRATE_MEM_OBJECTS, evs, event)
+
+    //• CL_MEM_OBJECT_ALLOCATION_FAILURE if there is a failure to allocate memory for the specified set of memory objects in mem_objects.
+}
+
+pub fn svm_alloc(
+    context: cl_context,
+    flags: cl_svm_mem_flags,
+    size: usize,
+    alignment: cl_uint,
+) -> CLResult<*mut c_void> {
+    let c = context.get_ref()?;
+
+    // CL_INVALID_OPERATION if no devices in context support SVM.
+    if !c.svm_supported() {
+        return Err(CL_INVALID_OPERATION);
+    }
+
+    // CL_INVALID_VALUE if values specified in flags do not follow rules described for supported
+    // values in the SVM Memory Flags table.
+    let valid_flags = cl_bitfield::from(
+        CL_MEM_READ_WRITE
+            | CL_MEM_WRITE_ONLY
+            | CL_MEM_READ_ONLY
+            | CL_MEM_SVM_FINE_GRAIN_BUFFER
+            | CL_MEM_SVM_ATOMICS,
+    );
+    let read_write_group =
+        cl_bitfield::from(CL_MEM_READ_WRITE | CL_MEM_WRITE_ONLY | CL_MEM_READ_ONLY);
+
+    if (flags & !valid_flags != 0)
+        || (flags & read_write_group).count_ones() > 1
+        // CL_MEM_SVM_ATOMICS is only valid together with CL_MEM_SVM_FINE_GRAIN_BUFFER
+        || (bit_check(flags, CL_MEM_SVM_ATOMICS)
+            && !bit_check(flags, CL_MEM_SVM_FINE_GRAIN_BUFFER))
+    {
+        return Err(CL_INVALID_VALUE);
+    }
+
+    // TODO: we don't advertise SVM atomics yet
+    if bit_check(flags, CL_MEM_SVM_ATOMICS) {
+        return Err(CL_INVALID_VALUE);
+    }
+
+    // CL_INVALID_VALUE if size is 0 or > CL_DEVICE_MAX_MEM_ALLOC_SIZE value for any device in
+    // context.
+    if size == 0 || c.devs.iter().any(|d| size as cl_ulong > d.max_mem_alloc()) {
+        return Err(CL_INVALID_VALUE);
+    }
+
+    // If alignment is 0, a default alignment will be used that is equal to the size of largest
+    // data type supported by the OpenCL implementation.
+    let alignment = if alignment == 0 {
+        std::mem::size_of::<[u64; 16]>()
+    } else {
+        alignment as usize
+    };
+
+    // CL_INVALID_VALUE if alignment is not a power of two.
+    let layout = Layout::from_size_align(size, alignment).map_err(|_| CL_INVALID_VALUE)?;
+
+    let ptr = unsafe { alloc::alloc(layout) }.cast::<c_void>();
+    if ptr.is_null() {
+        return Err(CL_OUT_OF_HOST_MEMORY);
+    }
+
+    c.add_svm_ptr(ptr, layout);
+    Ok(ptr)
+}
+
+pub fn svm_free(context: cl_context, svm_pointer: *mut c_void) -> CLResult<()> {
+    let c = context.get_ref()?;
+
+    // If a NULL pointer is passed in svm_pointer, no action occurs.
+    if svm_pointer.is_null() {
+        return Ok(());
+    }
+
+    // only pointers returned by clSVMAlloc for this context are freed
+    if let Some(layout) = c.remove_svm_ptr(svm_pointer) {
+        unsafe { alloc::dealloc(svm_pointer.cast(), layout) };
+    }
+
+    Ok(())
+}
+
+pub fn enqueue_svm_free(
+    command_queue: cl_command_queue,
+    num_svm_pointers: cl_uint,
+    svm_pointers: *mut *mut c_void,
+    pfn_free_func: Option<SVMFreeCb>,
+    user_data: *mut c_void,
+    num_events_in_wait_list: cl_uint,
+    event_wait_list: *const cl_event,
+    event: *mut cl_event,
+) -> CLResult<()> {
+    let q = command_queue.get_arc()?;
+    let evs = event_list_from_cl(&q, num_events_in_wait_list, event_wait_list)?;
+
+    // CL_INVALID_OPERATION if the device associated with command queue does not support SVM.
+    if !q.device.svm_supported() {
+        return Err(CL_INVALID_OPERATION);
+    }
+
+    // CL_INVALID_VALUE if num_svm_pointers is 0 and svm_pointers is non-NULL, or if svm_pointers
+    // is NULL and num_svm_pointers is not 0.
+    if (num_svm_pointers == 0) != svm_pointers.is_null() {
+        return Err(CL_INVALID_VALUE);
+    }
+
+    let svm_pointers = if svm_pointers.is_null() {
+        Vec::new()
+    } else {
+        unsafe { slice::from_raw_parts(svm_pointers, num_svm_pointers as usize) }.to_vec()
+    };
+
+    // only pointers returned by clSVMAlloc for this context can be freed
+    if svm_pointers.iter().any(|&ptr| {
+        !ptr.is_null()
+            && q.context
+                .find_svm_alloc(ptr)
+                .map_or(true, |(base, _)| base as usize != ptr as usize)
+    }) {
+        return Err(CL_INVALID_VALUE);
+    }
+
     create_and_queue(
         q,
-        CL_COMMAND_MIGRATE_MEM_OBJECTS,
+        CL_COMMAND_SVM_FREE,
         evs,
         event,
         false,
-        Box::new(|_, _| Ok(())),
+        Box::new(move |q, _| {
+            if let Some(cb) = pfn_free_func {
+                // application provided free function, it's also responsible for freeing the memory
+                // through clSVMFree if it was allocated that way.
+                unsafe {
+                    cb(
+                        cl_command_queue::from_ptr(Arc::as_ptr(q)),
+                        svm_pointers.len() as cl_uint,
+                        svm_pointers.as_ptr() as *mut _,
+                        user_data,
+                    )
+                };
+            } else {
+                for &ptr in &svm_pointers {
+                    if let Some(layout) = q.context.remove_svm_ptr(ptr) {
+                        unsafe { alloc::dealloc(ptr.cast(), layout) };
+                    }
+                }
+            }
+            Ok(())
+        }),
     )
+}
 
-    //• CL_MEM_OBJECT_ALLOCATION_FAILURE if there is a failure to allocate memory for the specified set of memory objects in mem_objects.
+pub fn enqueue_svm_migrate_mem(
+    command_queue: cl_command_queue,
+    num_svm_pointers: cl_uint,
+    svm_pointers: *mut *const c_void,
+    sizes: *const usize,
+    flags: cl_mem_migration_flags,
+    num_events_in_wait_list: cl_uint,
+    event_wait_list: *const cl_event,
+    event: *mut cl_event,
+) -> CLResult<()> {
+    let q = command_queue.get_arc()?;
+    let evs = event_list_from_cl(&q, num_events_in_wait_list, event_wait_list)?;
+
+    // CL_INVALID_OPERATION if the device associated with command queue does not support SVM.
+    if !q.device.svm_supported() {
+        return Err(CL_INVALID_OPERATION);
+    }
+
+    // CL_INVALID_VALUE if num_svm_pointers is zero or svm_pointers is NULL.
+    if num_svm_pointers == 0 || svm_pointers.is_null() {
+        return Err(CL_INVALID_VALUE);
+    }
+
+    // CL_INVALID_VALUE if flags is not 0 or is not any of the values described in the table above.
+    let valid_flags = (CL_MIGRATE_MEM_OBJECT_HOST | CL_MIGRATE_MEM_OBJECT_CONTENT_UNDEFINED)
+        as cl_mem_migration_flags;
+    if flags & !valid_flags != 0 {
+        return Err(CL_INVALID_VALUE);
+    }
+
+    let num_svm_pointers = num_svm_pointers as usize;
+    let svm_pointers = unsafe { slice::from_raw_parts(svm_pointers, num_svm_pointers) };
+    let sizes = if sizes.is_null() {
+        None
+    } else {
+        Some(unsafe { slice::from_raw_parts(sizes, num_svm_pointers) })
+    };
+
+    for (i, &ptr) in svm_pointers.iter().enumerate() {
+        // CL_INVALID_VALUE if any pointer in svm_pointers is NULL.
+        if ptr.is_null() {
+            return Err(CL_INVALID_VALUE);
+        }
+
+        // CL_INVALID_VALUE if sizes[i] is non-zero range [svm_pointers[i], svm_pointers[i]+sizes[i])
+        // is not contained within an existing clSVMAlloc allocation.
+        // If sizes is NULL or sizes[i] is zero, the entire allocation containing svm_pointers[i] is
+        // migrated.
+        let (base, alloc_size) = q.context.find_svm_alloc(ptr).ok_or(CL_INVALID_VALUE)?;
+        let size = sizes.map_or(0, |s| s[i]);
+        if size != 0
+            && (ptr as usize - base as usize)
+                .checked_add(size)
+                .map_or(true, |end| end > alloc_size)
+        {
+            return Err(CL_INVALID_VALUE);
+        }
+    }
+
+    // SVM allocations are plain system memory shared with the device, so there is nothing to
+    // migrate in either direction.
+    create_and_queue_noop(q, CL_COMMAND_SVM_MIGRATE_MEM, evs, event)
 }
 
 impl CLInfo<cl_pipe_info> for cl_mem {
@@ -2164,3 +2425,35 @@ impl CLInfo<cl_pipe_info> for cl_mem {
         Err(CL_INVALID_MEM_OBJECT)
     }
 }
+
+#[test]
+fn test_copy_overlaps() {
+    let ctx = crate::core::context::Context::new(Vec::new(), Default::default());
+    let new_buffer = || {
+        Mem::new_buffer(
+            ctx.clone(),
+            CL_MEM_READ_WRITE.into(),
+            64,
+            ptr::null_mut(),
+            Vec::new(),
+        )
+        .unwrap()
+    };
+
+    let buf = new_buffer();
+    assert!(copy_overlaps(&buf, 0, &buf, 8, 16));
+    assert!(copy_overlaps(&buf, 8, &buf, 0, 16));
+    // the regions are half open, so adjacent ones don't overlap
+    assert!(!copy_overlaps(&buf, 0, &buf, 16, 16));
+    assert!(!copy_overlaps(&buf, 16, &buf, 0, 16));
+
+    // sub buffers are compared by their offsets into the parent
+    let a = Mem::new_sub_buffer(buf.clone(), CL_MEM_READ_WRITE.into(), 0, 32);
+    let b = Mem::new_sub_buffer(buf.clone(), CL_MEM_READ_WRITE.into(), 16, 32);
+    assert!(copy_overlaps(&a, 16, &b, 0, 8));
+    assert!(!copy_overlaps(&a, 0, &b, 0, 16));
+    assert!(copy_overlaps(&a, 0, &buf, 8, 16));
+
+    // different buffers never overlap
+    assert!(!copy_overlaps(&buf, 0, &new_buffer(), 0, 64));
+}
@@ -23,7 +23,8 @@ impl CLInfo<cl_platform_info> for cl_platform_id {
             CL_PLATFORM_EXTENSIONS_WITH_VERSION => {
                 cl_prop::<Vec<cl_name_version>>(p.extensions.to_vec())
             }
-            CL_PLATFORM_HOST_TIMER_RESOLUTION => cl_prop::<cl_ulong>(0),
+            // we use os_time_get_nano for host timestamps
+            CL_PLATFORM_HOST_TIMER_RESOLUTION => cl_prop::<cl_ulong>(1),
             CL_PLATFORM_ICD_SUFFIX_KHR => cl_prop("MESA"),
             CL_PLATFORM_NAME => cl_prop("rusticl"),
             CL_PLATFORM_NUMERIC_VERSION => cl_prop::<cl_version>(CLVersion::Cl3_0 as u32),
@@ -96,10 +97,15 @@ pub fn get_platform_ids(
 
 pub fn unload_platform_compiler(platform: cl_platform_id) -> CLResult<()> {
     platform.get_ref()?;
-    // TODO unload the compiler
+    // This is only a hint. The only compiler state we keep around is libclc, which we still need
+    // to create kernels from already built programs, so there is nothing we can release.
     Ok(())
 }
 
+pub fn unload_compiler() -> CLResult<()> {
+    unload_platform_compiler(get_platform())
+}
+
 #[test]
 fn test_get_platform_info() {
     let mut s: usize = 0;
@@ -237,17 +237,25 @@ pub fn create_program_with_il(
     il: *const ::std::os::raw::c_void,
     length: usize,
 ) -> CLResult<cl_program> {
-    let _c = context.get_arc()?;
+    let c = context.get_arc()?;
 
     // CL_INVALID_VALUE if il is NULL or if length is zero.
     if il.is_null() || length == 0 {
         return Err(CL_INVALID_VALUE);
     }
 
-    //    let spirv = unsafe { slice::from_raw_parts(il.cast(), length) };
-    // TODO SPIR-V
-    //    Ok(cl_program::from_arc(Program::from_spirv(c, spirv)))
-    Err(CL_INVALID_OPERATION)
+    // CL_INVALID_VALUE if the length-byte memory pointed to by il does not contain well-formed
+    // intermediate language input appropriate for the deployment environment in which the OpenCL
+    // platform is running.
+    let spirv = unsafe { slice::from_raw_parts(il.cast(), length) };
+    let spirv = spirv::SPIRVBin::validate_header(spirv).ok_or(CL_INVALID_VALUE)?;
+
+    // CL_INVALID_OPERATION if no devices in context support intermediate language programs.
+    if !c.devs.iter().any(|d| d.il_supported()) {
+        return Err(CL_INVALID_OPERATION);
+    }
+
+    Ok(cl_program::from_arc(Program::from_spirv(c, &spirv)))
 }
 
 pub fn build_program(
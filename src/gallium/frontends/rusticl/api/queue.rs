@@ -1,4 +1,5 @@
 use crate::api::event::create_and_queue;
+use crate::api::event::create_and_queue_noop;
 use crate::api::icd::*;
 use crate::api::util::*;
 use crate::core::event::*;
@@ -7,6 +8,7 @@ use crate::core::queue::*;
 use mesa_rust_util::properties::*;
 use rusticl_opencl_gen::*;
 
+use std::env;
 use std::ptr;
 use std::sync::Arc;
 
@@ -50,6 +52,17 @@ fn supported_command_queue_properties(properties: cl_command_queue_properties) -
     properties & !valid_flags == 0
 }
 
+// The number of threads out-of-order queues run their commands on, set through
+// RUSTICL_QUEUE_THREADS. Only worth it if the driver can run work submitted from several
+// contexts concurrently, so it defaults to one.
+fn queue_workers() -> usize {
+    env::var("RUSTICL_QUEUE_THREADS")
+        .ok()
+        .and_then(|val| val.parse().ok())
+        .filter(|&t| t > 0)
+        .unwrap_or(1)
+}
+
 pub fn create_command_queue_impl(
     context: cl_context,
     device: cl_device_id,
@@ -79,6 +92,7 @@ pub fn create_command_queue_impl(
         d,
         properties,
         properties_v2,
+        queue_workers(),
     )?))
 }
 
@@ -95,48 +109,58 @@ pub fn create_command_queue_with_properties(
     device: cl_device_id,
     properties: *const cl_queue_properties,
 ) -> CLResult<cl_command_queue> {
-    let c = context.get_arc()?;
-    let d = device.get_arc()?;
+    context.get_ref()?;
+    device.get_ref()?;
 
     let mut queue_properties = cl_command_queue_properties::default();
     let properties = if properties.is_null() {
         None
     } else {
         let properties = Properties::from_ptr(properties).ok_or(CL_INVALID_PROPERTY)?;
+        let mut queue_size = None;
 
         for (k, v) in &properties.props {
             match *k as cl_uint {
                 CL_QUEUE_PROPERTIES => queue_properties = *v,
-                // CL_INVALID_QUEUE_PROPERTIES if values specified in properties are valid but are not
-                // supported by the device.
-                CL_QUEUE_SIZE => return Err(CL_INVALID_QUEUE_PROPERTIES),
-                _ => return Err(CL_INVALID_PROPERTY),
+                CL_QUEUE_SIZE => queue_size = Some(*v),
+                // CL_INVALID_VALUE if values specified in properties are not valid.
+                _ => return Err(CL_INVALID_VALUE),
             }
         }
 
+        // CL_QUEUE_SIZE can only be specified if CL_QUEUE_ON_DEVICE is set in CL_QUEUE_PROPERTIES.
+        if queue_size.is_some() && !bit_check(queue_properties, CL_QUEUE_ON_DEVICE) {
+            return Err(CL_INVALID_VALUE);
+        }
+
+        // If CL_QUEUE_ON_DEVICE is set, CL_QUEUE_OUT_OF_ORDER_EXEC_MODE_ENABLE must also be set.
+        // CL_QUEUE_ON_DEVICE_DEFAULT can only be used with CL_QUEUE_ON_DEVICE.
+        if bit_check(queue_properties, CL_QUEUE_ON_DEVICE)
+            && !bit_check(queue_properties, CL_QUEUE_OUT_OF_ORDER_EXEC_MODE_ENABLE)
+            || bit_check(queue_properties, CL_QUEUE_ON_DEVICE_DEFAULT)
+                && !bit_check(queue_properties, CL_QUEUE_ON_DEVICE)
+        {
+            return Err(CL_INVALID_VALUE);
+        }
+
+        // CL_INVALID_QUEUE_PROPERTIES if values specified in properties are valid but are not
+        // supported by the device.
+        // We don't support device side enqueue, so on-device queues can't be created.
+        if bit_check(queue_properties, CL_QUEUE_ON_DEVICE) {
+            return Err(CL_INVALID_QUEUE_PROPERTIES);
+        }
+
         Some(properties)
     };
 
-    Ok(cl_command_queue::from_arc(Queue::new(
-        c,
-        d,
-        queue_properties,
-        properties,
-    )?))
+    create_command_queue_impl(context, device, queue_properties, properties)
 }
 
 pub fn enqueue_marker(command_queue: cl_command_queue, event: *mut cl_event) -> CLResult<()> {
     let q = command_queue.get_arc()?;
 
     // TODO marker makes sure previous commands did complete
-    create_and_queue(
-        q,
-        CL_COMMAND_MARKER,
-        Vec::new(),
-        event,
-        false,
-        Box::new(|_, _| Ok(())),
-    )
+    create_and_queue_noop(q, CL_COMMAND_MARKER, Vec::new(), event)
 }
 
 pub fn enqueue_marker_with_wait_list(
@@ -149,14 +173,7 @@ pub fn enqueue_marker_with_wait_list(
     let evs = event_list_from_cl(&q, num_events_in_wait_list, event_wait_list)?;
 
     // TODO marker makes sure previous commands did complete
-    create_and_queue(
-        q,
-        CL_COMMAND_MARKER,
-        evs,
-        event,
-        false,
-        Box::new(|_, _| Ok(())),
-    )
+    create_and_queue_noop(q, CL_COMMAND_MARKER, evs, event)
 }
 
 pub fn enqueue_barrier(command_queue: cl_command_queue) -> CLResult<()> {
@@ -168,6 +185,33 @@ pub fn enqueue_barrier(command_queue: cl_command_queue) -> CLResult<()> {
     Ok(())
 }
 
+pub fn enqueue_wait_for_events(
+    command_queue: cl_command_queue,
+    num_events: cl_uint,
+    event_list: *const cl_event,
+) -> CLResult<()> {
+    let q = command_queue.get_arc()?;
+
+    // CL_INVALID_VALUE if num_events is zero or event_list is NULL.
+    if num_events == 0 || event_list.is_null() {
+        return Err(CL_INVALID_VALUE);
+    }
+
+    // CL_INVALID_EVENT if event objects specified in event_list are not valid events.
+    let evs = Event::from_cl_arr(event_list, num_events).map_err(|_| CL_INVALID_EVENT)?;
+
+    // CL_INVALID_CONTEXT if the context associated with command_queue and events in event_list are
+    // not the same.
+    if evs.iter().any(|e| e.context != q.context) {
+        return Err(CL_INVALID_CONTEXT);
+    }
+
+    // this works like a barrier waiting on the given events, as commands are executed in order
+    let e = Event::new(&q, CL_COMMAND_BARRIER, evs, Box::new(|_, _| Ok(())));
+    q.queue(e);
+    Ok(())
+}
+
 pub fn enqueue_barrier_with_wait_list(
     command_queue: cl_command_queue,
     num_events_in_wait_list: cl_uint,
@@ -287,11 +287,10 @@ pub fn check_cl_bool<T: PartialEq + TryInto<cl_uint>>(val: T) -> Option<bool> {
     Some(c == CL_TRUE)
 }
 
-pub fn event_list_from_cl(
-    q: &Arc<Queue>,
+fn check_event_wait_list(
     num_events_in_wait_list: cl_uint,
     event_wait_list: *const cl_event,
-) -> CLResult<Vec<Arc<Event>>> {
+) -> CLResult<()> {
     // CL_INVALID_EVENT_WAIT_LIST if event_wait_list is NULL and num_events_in_wait_list > 0, or
     // event_wait_list is not NULL and num_events_in_wait_list is 0, or if event objects in
     // event_wait_list are not valid events.
@@ -300,6 +299,15 @@ pub fn event_list_from_cl(
     {
         return Err(CL_INVALID_EVENT_WAIT_LIST);
     }
+    Ok(())
+}
+
+pub fn event_list_from_cl(
+    q: &Arc<Queue>,
+    num_events_in_wait_list: cl_uint,
+    event_wait_list: *const cl_event,
+) -> CLResult<Vec<Arc<Event>>> {
+    check_event_wait_list(num_events_in_wait_list, event_wait_list)?;
 
     let res = Event::from_cl_arr(event_wait_list, num_events_in_wait_list)
         .map_err(|_| CL_INVALID_EVENT_WAIT_LIST)?;
@@ -394,3 +402,28 @@ pub fn check_copy_overlap(
     /* Otherwise src and dst overlap. */
     true
 }
+
+#[test]
+fn test_event_wait_list_mismatch() {
+    // never dereferenced, only checked for NULL
+    let list: *const cl_event = std::ptr::NonNull::dangling().as_ptr();
+
+    assert_eq!(check_event_wait_list(0, std::ptr::null()), Ok(()));
+    assert_eq!(check_event_wait_list(2, list), Ok(()));
+    assert_eq!(
+        check_event_wait_list(2, std::ptr::null()),
+        Err(CL_INVALID_EVENT_WAIT_LIST)
+    );
+    assert_eq!(
+        check_event_wait_list(0, list),
+        Err(CL_INVALID_EVENT_WAIT_LIST)
+    );
+}
+
+#[test]
+fn test_empty_string_prop() {
+    // queries like CL_KERNEL_ATTRIBUTES return an empty string as a single NUL byte
+    assert_eq!(cl_prop::<&str>(""), [0]);
+    assert_eq!(cl_prop::<String>(String::new()), [0]);
+    assert_eq!(cl_prop::<&str>("a,b"), b"a,b\0");
+}
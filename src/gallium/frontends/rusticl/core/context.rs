@@ -10,9 +10,15 @@ use mesa_rust::pipe::screen::ResourceType;
 use mesa_rust_util::properties::Properties;
 use rusticl_opencl_gen::*;
 
+use std::alloc::dealloc;
+use std::alloc::Layout;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::convert::TryInto;
 use std::os::raw::c_void;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::sync::Mutex;
 
@@ -21,6 +27,11 @@ pub struct Context {
     pub devs: Vec<Arc<Device>>,
     pub properties: Properties<cl_context_properties>,
     pub dtors: Mutex<Vec<Box<dyn Fn(cl_context)>>>,
+    svm_ptrs: Mutex<BTreeMap<usize, Layout>>,
+    // memory objects are allocated on every device, so this is the same for all of them
+    mem_usage: AtomicU64,
+    // addresses of all live memory objects, only tracked for the argument audit
+    debug_mems: Option<Mutex<HashSet<usize>>>,
 }
 
 impl_cl_type_trait!(cl_context, Context, CL_INVALID_CONTEXT);
@@ -35,9 +46,96 @@ impl Context {
             devs: devs,
             properties: properties,
             dtors: Mutex::new(Vec::new()),
+            svm_ptrs: Mutex::new(BTreeMap::new()),
+            mem_usage: AtomicU64::new(0),
+            debug_mems: debug_args().then(|| Mutex::new(HashSet::new())),
         })
     }
 
+    pub fn svm_supported(&self) -> bool {
+        self.devs.iter().any(|d| d.svm_supported())
+    }
+
+    pub fn image_supported(&self) -> bool {
+        self.devs.iter().any(|d| d.image_supported())
+    }
+
+    /// Bytes allocated on each device for live memory objects of this context.
+    pub fn mem_usage(&self) -> u64 {
+        self.mem_usage.load(Ordering::Relaxed)
+    }
+
+    /// Accounts for `size` bytes of new allocations, fails if that would exceed the global memory
+    /// of any device in the context.
+    ///
+    /// As every memory object takes the same amount of memory on each device, the smallest device
+    /// limits all of them. A context without devices never allocates device memory, so nothing
+    /// limits it.
+    pub fn reserve_mem(&self, size: usize) -> CLResult<()> {
+        let max = self
+            .devs
+            .iter()
+            .map(|d| d.global_mem_size())
+            .min()
+            .unwrap_or(u64::MAX);
+        self.reserve_mem_within(size, max)
+    }
+
+    fn reserve_mem_within(&self, size: usize, max: u64) -> CLResult<()> {
+        // the update is a single compare and swap loop on the counter, so concurrent reservations
+        // can't together exceed max and we don't need any ordering with other memory
+        self.mem_usage
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |usage| {
+                usage.checked_add(size as u64).filter(|&new| new <= max)
+            })
+            .map(|_| ())
+            .map_err(|_| CL_MEM_OBJECT_ALLOCATION_FAILURE)
+    }
+
+    pub fn release_mem(&self, size: usize) {
+        self.mem_usage.fetch_sub(size as u64, Ordering::Relaxed);
+    }
+
+    pub fn add_debug_mem(&self, mem: &Arc<Mem>) {
+        if let Some(mems) = &self.debug_mems {
+            mems.lock().unwrap().insert(Arc::as_ptr(mem) as usize);
+        }
+    }
+
+    pub fn remove_debug_mem(&self, mem: &Mem) {
+        if let Some(mems) = &self.debug_mems {
+            mems.lock().unwrap().remove(&(mem as *const Mem as usize));
+        }
+    }
+
+    // whether ptr is a memory object of this context, only works with the argument audit enabled
+    pub fn is_debug_mem(&self, ptr: usize) -> bool {
+        self.debug_mems
+            .as_ref()
+            .map_or(false, |mems| mems.lock().unwrap().contains(&ptr))
+    }
+
+    pub fn add_svm_ptr(&self, ptr: *mut c_void, layout: Layout) {
+        self.svm_ptrs.lock().unwrap().insert(ptr as usize, layout);
+    }
+
+    // returns the base pointer and the size of the SVM allocation containing ptr
+    pub fn find_svm_alloc(&self, ptr: *const c_void) -> Option<(*const c_void, usize)> {
+        let ptr = ptr as usize;
+        let lock = self.svm_ptrs.lock().unwrap();
+        let (&base, layout) = lock.range(..=ptr).next_back()?;
+
+        if ptr < base + layout.size() {
+            Some((base as *const c_void, layout.size()))
+        } else {
+            None
+        }
+    }
+
+    pub fn remove_svm_ptr(&self, ptr: *const c_void) -> Option<Layout> {
+        self.svm_ptrs.lock().unwrap().remove(&(ptr as usize))
+    }
+
     pub fn create_buffer(
         &self,
         size: usize,
@@ -60,7 +158,7 @@ impl Context {
                 resource = dev.screen().resource_create_buffer(adj_size, res_type)
             }
 
-            let resource = resource.ok_or(CL_OUT_OF_RESOURCES);
+            let resource = resource.ok_or(CL_MEM_OBJECT_ALLOCATION_FAILURE);
             res.insert(Arc::clone(dev), Arc::new(resource?));
         }
 
@@ -121,7 +219,7 @@ impl Context {
                 )
             }
 
-            let resource = resource.ok_or(CL_OUT_OF_RESOURCES);
+            let resource = resource.ok_or(CL_MEM_OBJECT_ALLOCATION_FAILURE);
             res.insert(Arc::clone(dev), Arc::new(resource?));
         }
 
@@ -152,5 +250,32 @@ impl Drop for Context {
             .iter()
             .rev()
             .for_each(|cb| cb(cl));
+
+        // free all SVM allocations the application didn't free
+        for (ptr, layout) in self.svm_ptrs.get_mut().unwrap().iter() {
+            unsafe { dealloc(*ptr as *mut u8, *layout) };
+        }
     }
 }
+
+#[test]
+fn test_reserve_mem() {
+    let ctx = Context::new(Vec::new(), Default::default());
+
+    assert!(ctx.reserve_mem_within(60, 100).is_ok());
+    assert_eq!(
+        ctx.reserve_mem_within(60, 100),
+        Err(CL_MEM_OBJECT_ALLOCATION_FAILURE)
+    );
+    assert_eq!(ctx.mem_usage(), 60);
+
+    ctx.release_mem(60);
+    assert_eq!(ctx.mem_usage(), 0);
+    assert!(ctx.reserve_mem_within(60, 100).is_ok());
+    ctx.release_mem(60);
+
+    // without a device there is nothing to run out of
+    assert!(ctx.reserve_mem(usize::MAX).is_ok());
+    ctx.release_mem(usize::MAX);
+    assert_eq!(ctx.mem_usage(), 0);
+}
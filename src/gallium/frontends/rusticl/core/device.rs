@@ -39,6 +39,7 @@ pub struct Device {
     pub clc_features: Vec<cl_name_version>,
     pub formats: HashMap<cl_image_format, HashMap<cl_mem_object_type, cl_mem_flags>>,
     pub lib_clc: NirShader,
+    printf_buffer_size: usize,
     helper_ctx: Mutex<PipeContext>,
 }
 
@@ -155,6 +156,24 @@ impl<'a> HelperContextWrapper for HelperContext<'a> {
 
 impl_cl_type_trait!(cl_device_id, Device, CL_INVALID_DEVICE);
 
+// callers index the block sizes by dimensions up to max_grid_dimensions, so make sure they can, a
+// dimension the driver doesn't report can only have a block size of 1.
+fn pad_block_sizes(sizes: Vec<u64>, dims: cl_uint) -> Vec<usize> {
+    let mut res: Vec<usize> = sizes.into_iter().map(|v| v as usize).collect();
+    let dims = max(res.len(), dims as usize);
+    res.resize(dims, 1);
+    res
+}
+
+// the most subgroups a work-group of `threads` threads can be split into
+fn max_subgroups_in(threads: u32, subgroup_size: u32) -> u32 {
+    if subgroup_size == 0 {
+        return 0;
+    }
+    // the last subgroup doesn't have to be full
+    (threads + subgroup_size - 1) / subgroup_size
+}
+
 impl Device {
     fn new(screen: Arc<PipeScreen>) -> Option<Arc<Device>> {
         if !Self::check_valid(&screen) {
@@ -180,6 +199,7 @@ impl Device {
             clc_features: Vec::new(),
             formats: HashMap::new(),
             lib_clc: lib_clc?,
+            printf_buffer_size: Self::parse_env_printf_buffer_size().unwrap_or(1024 * 1024),
         };
 
         d.fill_format_tables();
@@ -369,6 +389,14 @@ impl Device {
         mk_cl_version(major, minor, 0).try_into().ok()
     }
 
+    fn parse_env_printf_buffer_size() -> Option<usize> {
+        let val = env::var("RUSTICL_PRINTF_BUFFER_SIZE").ok()?;
+        let size: usize = val.parse().ok()?;
+        // the buffer has to hold at least its length header, and anything below the 1 KB the
+        // EMBEDDED profile requires isn't useful anyway
+        Some(max(size, 1024))
+    }
+
     // TODO add CLC checks
     fn check_version(&mut self) {
         let exts: Vec<&str> = self.extension_string.split(' ').collect();
@@ -481,6 +509,10 @@ impl Device {
             add_ext(1, 0, 0, "cl_khr_fp64", "__opencl_c_fp64");
         }
 
+        // cl_khr_fp16 also requires conversions to half with explicit rounding modes, which we
+        // don't lower yet (nir_lower_fp16_casts), so we can't advertise it even if the driver
+        // supports fp16 math.
+
         if self.long_supported() {
             let ext = if self.embedded { "cles_khr_int64" } else { "" };
 
@@ -567,6 +599,22 @@ impl Device {
         */
     }
 
+    pub fn il_supported(&self) -> bool {
+        // TODO spirv
+        false
+    }
+
+    // cl_khr_int64_base_atomics and cl_khr_int64_extended_atomics aren't advertised, as OpenCL C
+    // doesn't get the features enabled yet, but SPIR-V modules can use them already.
+    pub fn int64_atomics_supported(&self) -> bool {
+        self.shader_param(pipe_shader_cap::PIPE_SHADER_CAP_INT64_ATOMICS) != 0
+    }
+
+    pub fn non_uniform_work_group_supported(&self) -> bool {
+        // TODO: launches would need to split off the remainder work-groups
+        false
+    }
+
     pub fn long_supported(&self) -> bool {
         self.screen.param(pipe_cap::PIPE_CAP_INT64) == 1
     }
@@ -653,7 +701,7 @@ impl Device {
         let v: Vec<u64> = self
             .screen
             .compute_param(pipe_compute_cap::PIPE_COMPUTE_CAP_MAX_BLOCK_SIZE);
-        v.into_iter().map(|v| v as usize).collect()
+        pad_block_sizes(v, self.max_grid_dimensions())
     }
 
     pub fn max_clock_freq(&self) -> cl_uint {
@@ -701,7 +749,7 @@ impl Device {
     }
 
     pub fn printf_buffer_size(&self) -> usize {
-        1024 * 1024
+        self.printf_buffer_size
     }
 
     pub fn screen(&self) -> &Arc<PipeScreen> {
@@ -715,6 +763,41 @@ impl Device {
         )
     }
 
+    pub fn subgroups_supported(&self) -> bool {
+        // TODO: the subgroup size cap is enough for the device queries, but kernels using
+        // subgroups need their subgroups lowered and clGetKernelSubGroupInfo doesn't answer the
+        // subgroup count queries yet.
+        false
+    }
+
+    pub fn max_subgroups(&self) -> u32 {
+        if !self.subgroups_supported() {
+            return 0;
+        }
+        max_subgroups_in(self.max_threads_per_block() as u32, self.subgroups())
+    }
+
+    pub fn subgroups_independent_forward_progress(&self) -> bool {
+        // we can't know whether hardware guarantees it, so don't promise anything
+        false
+    }
+
+    pub fn svm_supported(&self) -> bool {
+        self.screen.param(pipe_cap::PIPE_CAP_SYSTEM_SVM) == 1
+    }
+
+    pub fn svm_caps(&self) -> cl_device_svm_capabilities {
+        // we only support SVM on top of system SVM for now, which means plain host allocations can
+        // be accessed by the device directly.
+        if self.svm_supported() {
+            (CL_DEVICE_SVM_COARSE_GRAIN_BUFFER
+                | CL_DEVICE_SVM_FINE_GRAIN_BUFFER
+                | CL_DEVICE_SVM_FINE_GRAIN_SYSTEM) as cl_device_svm_capabilities
+        } else {
+            0
+        }
+    }
+
     pub fn unified_memory(&self) -> bool {
         self.screen.param(pipe_cap::PIPE_CAP_UMA) == 1
     }
@@ -742,7 +825,24 @@ impl Device {
             images_read_write: self.image_read_write_supported(),
             images_write_3d: self.image_3d_write_supported(),
             intel_subgroups: false,
-            subgroups: false,
+            subgroups: self.subgroups_supported(),
         }
     }
 }
+
+#[test]
+fn test_pad_block_sizes() {
+    assert_eq!(pad_block_sizes(vec![256, 256], 3), [256, 256, 1]);
+    assert_eq!(pad_block_sizes(vec![256, 256, 64], 3), [256, 256, 64]);
+    // more sizes than dimensions don't hurt anybody
+    assert_eq!(pad_block_sizes(vec![256, 256, 64], 2), [256, 256, 64]);
+}
+
+#[test]
+fn test_max_subgroups() {
+    assert_eq!(max_subgroups_in(1024, 32), 32);
+    assert_eq!(max_subgroups_in(1000, 32), 32);
+    assert_eq!(max_subgroups_in(16, 32), 1);
+    // drivers not reporting a subgroup size can't have subgroups
+    assert_eq!(max_subgroups_in(1024, 0), 0);
+}
@@ -25,10 +25,20 @@ static_assert!(CL_QUEUED == 3);
 
 pub type EventSig = Box<dyn Fn(&Arc<Queue>, &PipeContext) -> CLResult<()>>;
 
+#[derive(Clone, Copy, Default)]
+pub struct ProfilingTimestamps {
+    pub queued: cl_ulong,
+    pub submit: cl_ulong,
+    pub start: cl_ulong,
+    pub end: cl_ulong,
+}
+
 struct EventMutState {
     status: cl_int,
     cbs: [Vec<(EventCB, *mut c_void)>; 3],
     fence: Option<PipeFence>,
+    times: ProfilingTimestamps,
+    failure: Option<String>,
 }
 
 #[repr(C)]
@@ -56,6 +66,11 @@ impl Event {
         deps: Vec<Arc<Event>>,
         work: EventSig,
     ) -> Arc<Event> {
+        let mut times = ProfilingTimestamps::default();
+        if Self::queue_profiling(queue) {
+            times.queued = queue.device.screen().get_timestamp();
+        }
+
         Arc::new(Self {
             base: CLObjectBase::new(),
             context: queue.context.clone(),
@@ -66,6 +81,8 @@ impl Event {
                 status: CL_QUEUED as cl_int,
                 cbs: [Vec::new(), Vec::new(), Vec::new()],
                 fence: None,
+                times: times,
+                failure: None,
             }),
             work: Some(work),
             cv: Condvar::new(),
@@ -83,6 +100,8 @@ impl Event {
                 status: CL_SUBMITTED as cl_int,
                 cbs: [Vec::new(), Vec::new(), Vec::new()],
                 fence: None,
+                times: ProfilingTimestamps::default(),
+                failure: None,
             }),
             work: None,
             cv: Condvar::new(),
@@ -102,6 +121,33 @@ impl Event {
         self.state().status
     }
 
+    fn queue_profiling(q: &Queue) -> bool {
+        q.props & CL_QUEUE_PROFILING_ENABLE as cl_command_queue_properties != 0
+    }
+
+    fn profiling_enabled(&self) -> bool {
+        self.queue.as_deref().map_or(false, Self::queue_profiling)
+    }
+
+    fn timestamp(&self) -> cl_ulong {
+        self.queue.as_ref().unwrap().device.screen().get_timestamp()
+    }
+
+    /// Returns all profiling timestamps at once, or `None` if they are not available because
+    /// profiling wasn't enabled on the queue, this is a user event or the command didn't complete
+    /// yet.
+    pub fn profiling(&self) -> Option<ProfilingTimestamps> {
+        if !self.profiling_enabled() {
+            return None;
+        }
+
+        let lock = self.state();
+        if lock.status != CL_COMPLETE as cl_int {
+            return None;
+        }
+        Some(lock.times)
+    }
+
     fn set_status(&self, lock: &mut MutexGuard<EventMutState>, new: cl_int) {
         lock.status = new;
         self.cv.notify_all();
@@ -118,6 +164,18 @@ impl Event {
         self.set_status(&mut lock, status);
     }
 
+    /// Fails the event with `status` and records why it failed.
+    pub fn set_failure(&self, status: cl_int, detail: String) {
+        let mut lock = self.state();
+        lock.failure = Some(detail);
+        self.set_status(&mut lock, status);
+    }
+
+    /// Returns a human readable reason for why the event failed, if it failed inside rusticl.
+    pub fn failure_detail(&self) -> Option<String> {
+        self.state().failure.clone()
+    }
+
     pub fn is_error(&self) -> bool {
         self.status() < 0
     }
@@ -140,6 +198,9 @@ impl Event {
         while lock.status >= CL_SUBMITTED as cl_int {
             if lock.fence.is_some() {
                 lock.fence.as_ref().unwrap().wait();
+                if self.profiling_enabled() {
+                    lock.times.end = self.timestamp();
+                }
                 // so we trigger all cbs
                 self.set_status(&mut lock, CL_RUNNING as cl_int);
                 self.set_status(&mut lock, CL_COMPLETE as cl_int);
@@ -161,6 +222,12 @@ impl Event {
         let mut lock = self.state();
         let status = lock.status;
         if status == CL_QUEUED as cl_int {
+            if self.profiling_enabled() {
+                let now = self.timestamp();
+                lock.times.submit = now;
+                lock.times.start = now;
+            }
+
             let new = self.work.as_ref().map_or(
                 // if there is no work
                 CL_SUBMITTED as cl_int,
@@ -168,7 +235,13 @@ impl Event {
                     let res = w(self.queue.as_ref().unwrap(), ctx).err().map_or(
                         // if there is an error, negate it
                         CL_SUBMITTED as cl_int,
-                        |e| e,
+                        |e| {
+                            lock.failure = Some(format!(
+                                "command {:#x} failed with error {}",
+                                self.cmd_type, e
+                            ));
+                            e
+                        },
                     );
                     lock.fence = Some(ctx.flush());
                     res
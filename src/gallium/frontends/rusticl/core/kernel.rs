@@ -1,5 +1,6 @@
 use crate::api::icd::*;
 use crate::api::util::cl_prop;
+use crate::core::context::*;
 use crate::core::device::*;
 use crate::core::event::*;
 use crate::core::format::*;
@@ -12,13 +13,13 @@ use mesa_rust::compiler::clc::*;
 use mesa_rust::compiler::nir::*;
 use mesa_rust::pipe::context::RWFlags;
 use mesa_rust::pipe::context::ResourceMapType;
+use mesa_rust::pipe::resource::PipeResource;
 use mesa_rust::pipe::screen::ResourceType;
 use mesa_rust_gen::*;
 use mesa_rust_util::math::*;
 use mesa_rust_util::serialize::*;
 use rusticl_opencl_gen::*;
 
-use std::cell::RefCell;
 use std::cmp;
 use std::collections::HashMap;
 use std::collections::HashSet;
@@ -26,8 +27,12 @@ use std::convert::TryInto;
 use std::os::raw::c_void;
 use std::ptr;
 use std::slice;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
 
 // ugh, we are not allowed to take refs, so...
 #[derive(Clone)]
@@ -37,6 +42,71 @@ pub enum KernelArgValue {
     MemObject(Arc<Mem>),
     Sampler(Arc<Sampler>),
     LocalMem(usize),
+    // an SVM pointer set through clSetKernelArgSVMPointer
+    SvmPointer(usize),
+}
+
+// the values of all args of a kernel, applications might set them from multiple threads, so we
+// have to lock them
+pub struct KernelArgValues {
+    values: Vec<Mutex<Option<KernelArgValue>>>,
+    // the amount of args without a value, so launches don't have to check each of them
+    unset: AtomicUsize,
+}
+
+impl KernelArgValues {
+    fn new(count: usize) -> Self {
+        Self {
+            // can't use vec!...
+            values: (0..count).map(|_| Mutex::new(None)).collect(),
+            unset: AtomicUsize::new(count),
+        }
+    }
+
+    pub fn lock(&self, idx: cl_uint) -> MutexGuard<Option<KernelArgValue>> {
+        self.values[idx as usize].lock().unwrap()
+    }
+
+    pub fn set(&self, idx: cl_uint, val: KernelArgValue) {
+        if self.lock(idx).replace(val).is_none() {
+            self.unset.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn clear(&self) {
+        for v in &self.values {
+            if v.lock().unwrap().take().is_some() {
+                self.unset.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn all_set(&self) -> bool {
+        self.unset.load(Ordering::Relaxed) == 0
+    }
+
+    fn has_svm_pointers(&self) -> bool {
+        self.values
+            .iter()
+            .any(|v| matches!(*v.lock().unwrap(), Some(KernelArgValue::SvmPointer(_))))
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &Mutex<Option<KernelArgValue>>> {
+        self.values.iter()
+    }
+}
+
+impl Clone for KernelArgValues {
+    fn clone(&self) -> Self {
+        Self {
+            values: self
+                .values
+                .iter()
+                .map(|v| Mutex::new(v.lock().unwrap().clone()))
+                .collect(),
+            unset: AtomicUsize::new(self.unset.load(Ordering::Relaxed)),
+        }
+    }
 }
 
 #[derive(Hash, PartialEq, Eq, Clone, Copy)]
@@ -83,6 +153,8 @@ impl KernelArg {
             .variables_with_mode(
                 nir_variable_mode::nir_var_uniform | nir_variable_mode::nir_var_image,
             )
+            // inline samplers are program-scope constants and not kernel args
+            .filter(|v| !is_inline_sampler(v))
             .map(|v| (v.data.location, v))
             .collect();
         let mut res = Vec::new();
@@ -242,16 +314,34 @@ impl InternalKernelArg {
     }
 }
 
+// everything clGetKernelInfo reports, for users of the core crate
+pub struct KernelInfo {
+    pub name: String,
+    pub num_args: cl_uint,
+    pub attributes: String,
+    pub program: Arc<Program>,
+    pub context: Arc<Context>,
+}
+
 #[repr(C)]
 pub struct Kernel {
     pub base: CLObjectBase<CL_INVALID_KERNEL>,
     pub prog: Arc<Program>,
     pub name: String,
     pub args: Vec<KernelArg>,
-    pub values: Vec<RefCell<Option<KernelArgValue>>>,
+    pub values: KernelArgValues,
     pub work_group_size: [usize; 3],
+    pub subgroup_size: usize,
     pub attributes_string: String,
+    // we only support SVM on top of system SVM, so those pointers can be accessed by the device
+    // directly and don't have to be made resident on launch.
+    svm_ptrs: Mutex<Vec<usize>>,
+    fine_grain_system_svm: AtomicBool,
     internal_args: Vec<InternalKernelArg>,
+    // the content of the constant buffer only depends on the nir, so upload it once per device
+    constant_bufs: Mutex<HashMap<Arc<Device>, Arc<PipeResource>>>,
+    // overrides of the work group size multiple used when picking a local size
+    local_multiples: Mutex<HashMap<Arc<Device>, u32>>,
     nirs: HashMap<Arc<Device>, NirShader>,
 }
 
@@ -371,6 +461,10 @@ fn lower_and_optimize_nir_pre_inputs(dev: &Device, nir: &mut NirShader, lib_clc:
     opt_nir(nir, dev);
 }
 
+fn is_inline_sampler(var: &nir_variable) -> bool {
+    unsafe { glsl_type_is_sampler(var.type_) && var.data.anon_1.sampler.is_inline_sampler() != 0 }
+}
+
 extern "C" fn can_remove_var(var: *mut nir_variable, _: *mut c_void) -> bool {
     unsafe {
         let var = var.as_ref().unwrap();
@@ -412,12 +506,8 @@ fn lower_and_optimize_nir_late(
     for v in nir
         .variables_with_mode(nir_variable_mode::nir_var_uniform | nir_variable_mode::nir_var_image)
     {
-        if unsafe { !glsl_type_is_sampler(v.type_) } {
-            last_loc = v.data.location;
-            continue;
-        }
-        let s = unsafe { v.data.anon_1.sampler };
-        if s.is_inline_sampler() != 0 {
+        if is_inline_sampler(v) {
+            let s = unsafe { v.data.anon_1.sampler };
             last_loc += 1;
             v.data.location = last_loc;
 
@@ -693,25 +783,36 @@ fn extract<'a, const S: usize>(buf: &'a mut &[u8]) -> &'a [u8; S] {
     val.try_into().unwrap()
 }
 
-fn optimize_local_size(d: &Device, grid: &mut [u32; 3], block: &mut [u32; 3]) {
-    let mut threads = d.max_threads_per_block() as u32;
-    let dim_threads = d.max_block_sizes();
-    let subgroups = d.subgroups();
+// pointers are as big as the address bits of the device, which don't have to match the host ones
+fn ptr_bytes(ptr: usize, size: usize) -> Vec<u8> {
+    if size == 4 {
+        (ptr as u32).to_ne_bytes().to_vec()
+    } else {
+        (ptr as u64).to_ne_bytes().to_vec()
+    }
+}
 
+fn optimize_local_size(
+    mut threads: u32,
+    dim_threads: &[usize],
+    subgroups: u32,
+    grid: &mut [u64; 3],
+    block: &mut [u32; 3],
+) {
     if !block.contains(&0) {
         for i in 0..3 {
             // we already made sure everything is fine
-            grid[i] /= block[i];
+            grid[i] /= block[i] as u64;
         }
         return;
     }
 
     for i in 0..3 {
         let t = cmp::min(threads, dim_threads[i] as u32);
-        let gcd = gcd(t, grid[i]);
+        let gcd = gcd(t as u64, grid[i]) as u32;
 
         block[i] = gcd;
-        grid[i] /= gcd;
+        grid[i] /= gcd as u64;
 
         // update limits
         threads /= block[i];
@@ -721,8 +822,8 @@ fn optimize_local_size(d: &Device, grid: &mut [u32; 3], block: &mut [u32; 3]) {
     let total_threads = block[0] * block[1] * block[2];
     if threads != 1 && total_threads < subgroups {
         for i in 0..3 {
-            if grid[i] * total_threads < threads {
-                block[i] *= grid[i];
+            if grid[i] * (total_threads as u64) < threads as u64 {
+                block[i] *= grid[i] as u32;
                 grid[i] = 1;
                 // can only do it once as nothing is cleanly divisible
                 break;
@@ -731,6 +832,58 @@ fn optimize_local_size(d: &Device, grid: &mut [u32; 3], block: &mut [u32; 3]) {
     }
 }
 
+// returns the (block, grid) pair used to launch k, where grid is the number of work groups in each
+// dimension. If the application didn't specify a local size we pick one, taking the required
+// work group size of the kernel into account. The api layer has to validate the sizes beforehand,
+// this only fails if the number of work groups doesn't fit into 32 bits.
+pub fn launch_geometry(
+    d: &Arc<Device>,
+    k: &Kernel,
+    global: &[usize],
+    local: &[usize],
+) -> CLResult<([u32; 3], [u32; 3])> {
+    geometry(
+        d.max_threads_per_block() as u32,
+        &d.max_block_sizes(),
+        k.preferred_local_multiple(d),
+        &k.work_group_size,
+        global,
+        local,
+    )
+}
+
+// launch_geometry without the device and kernel, so it can be tested with any limits
+fn geometry(
+    max_threads: u32,
+    max_block: &[usize],
+    multiple: u32,
+    work_group_size: &[usize; 3],
+    global: &[usize],
+    local: &[usize],
+) -> CLResult<([u32; 3], [u32; 3])> {
+    let mut block = create_kernel_arr::<u32>(local, 1);
+    // the global size can exceed 32 bits on 64 bit devices, only the amount of work groups can't
+    let mut grid = create_kernel_arr::<u64>(global, 1);
+
+    // the api layer turns empty launches into no-ops already, but we can't pick a block for them
+    // without dividing by zero
+    if grid.contains(&0) {
+        return Ok(([1; 3], [0; 3]));
+    }
+
+    if block.contains(&0) && !work_group_size.contains(&0) {
+        block = create_kernel_arr::<u32>(work_group_size, 1);
+    }
+
+    optimize_local_size(max_threads, max_block, multiple, &mut grid, &mut block);
+
+    let mut res = [0; 3];
+    for i in 0..3 {
+        res[i] = grid[i].try_into().map_err(|_| CL_OUT_OF_RESOURCES)?;
+    }
+    Ok((block, res))
+}
+
 impl Kernel {
     pub fn new(name: String, prog: Arc<Program>, args: Vec<spirv::SPIRVKernelArg>) -> Arc<Kernel> {
         let (mut nirs, args, internal_args, attributes_string) =
@@ -739,9 +892,9 @@ impl Kernel {
         let nir = nirs.values_mut().next().unwrap();
         let wgs = nir.workgroup_size();
         let work_group_size = [wgs[0] as usize, wgs[1] as usize, wgs[2] as usize];
+        let subgroup_size = nir.subgroup_size() as usize;
 
-        // can't use vec!...
-        let values = args.iter().map(|_| RefCell::new(None)).collect();
+        let values = KernelArgValues::new(args.len());
 
         // increase ref
         prog.kernel_count.fetch_add(1, Ordering::Relaxed);
@@ -752,14 +905,130 @@ impl Kernel {
             name: name,
             args: args,
             work_group_size: work_group_size,
+            subgroup_size: subgroup_size,
             attributes_string: attributes_string,
             values: values,
+            svm_ptrs: Mutex::new(Vec::new()),
+            fine_grain_system_svm: AtomicBool::new(false),
             internal_args: internal_args,
+            constant_bufs: Mutex::new(HashMap::new()),
+            local_multiples: Mutex::new(HashMap::new()),
             // caller has to verify all kernels have the same sig
             nirs: nirs,
         })
     }
 
+    pub fn info(&self) -> KernelInfo {
+        KernelInfo {
+            name: self.name.clone(),
+            num_args: self.args.len() as cl_uint,
+            attributes: self.attributes_string.clone(),
+            program: self.prog.clone(),
+            context: self.prog.context.clone(),
+        }
+    }
+
+    pub fn set_svm_ptrs(&self, ptrs: &[*const c_void]) {
+        *self.svm_ptrs.lock().unwrap() = ptrs.iter().map(|&p| p as usize).collect();
+    }
+
+    pub fn set_fine_grain_system_svm(&self, val: bool) {
+        self.fine_grain_system_svm.store(val, Ordering::Relaxed);
+    }
+
+    // the pointers are stored behind a lock, so we can only hand out a copy
+    pub fn exec_info_svm_ptrs(&self) -> Vec<*const c_void> {
+        self.svm_ptrs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|&p| p as *const c_void)
+            .collect()
+    }
+
+    pub fn fine_grain_system(&self) -> bool {
+        self.fine_grain_system_svm.load(Ordering::Relaxed)
+    }
+
+    pub fn set_arg(&self, idx: cl_uint, val: KernelArgValue) {
+        self.values.set(idx, val);
+    }
+
+    pub fn all_args_set(&self) -> bool {
+        self.values.all_set()
+    }
+
+    /// Makes launches on `dev` without a local size try to use work groups of a multiple of `n`
+    /// work-items instead of the subgroup size. Passing 0 removes the override.
+    pub fn set_preferred_local_multiple(&self, dev: &Arc<Device>, n: u32) {
+        let mut multiples = self.local_multiples.lock().unwrap();
+        if n == 0 {
+            multiples.remove(dev);
+        } else {
+            multiples.insert(dev.clone(), n);
+        }
+    }
+
+    pub fn preferred_local_multiple(&self, dev: &Arc<Device>) -> u32 {
+        self.local_multiples
+            .lock()
+            .unwrap()
+            .get(dev)
+            .copied()
+            .unwrap_or_else(|| dev.subgroups())
+    }
+
+    // unsets all args, so the kernel can't be enqueued again before they are set anew
+    pub fn clear_args(&self) {
+        self.values.clear();
+    }
+
+    // whether the kernel got SVM pointers as args or was told to access SVM allocations or system
+    // pointers through exec info
+    pub fn uses_svm(&self) -> bool {
+        !self.svm_ptrs.lock().unwrap().is_empty()
+            || self.fine_grain_system_svm.load(Ordering::Relaxed)
+            || self.values.has_svm_pointers()
+    }
+
+    fn constant_buffer(&self, dev: &Arc<Device>) -> CLResult<Arc<PipeResource>> {
+        let mut bufs = self.constant_bufs.lock().unwrap();
+        if let Some(res) = bufs.get(dev) {
+            return Ok(res.clone());
+        }
+
+        let buf = self.nirs.get(dev).unwrap().get_constant_buffer();
+        let res = Arc::new(
+            dev.screen()
+                .resource_create_buffer(buf.len() as u32, ResourceType::Normal)
+                .ok_or(CL_OUT_OF_RESOURCES)?,
+        );
+        dev.helper_ctx()
+            .exec(|ctx| ctx.buffer_subdata(&res, 0, buf.as_ptr().cast(), buf.len() as u32))
+            .wait();
+
+        bufs.insert(dev.clone(), res.clone());
+        Ok(res)
+    }
+
+    // creates the state launching the kernel on dev depends on upfront, so that the first launch
+    // doesn't have to
+    pub fn prepare(&self, dev: &Arc<Device>) -> CLResult<()> {
+        if !self.has_build(dev) {
+            return Err(CL_INVALID_PROGRAM_EXECUTABLE);
+        }
+
+        if self
+            .internal_args
+            .iter()
+            .any(|a| a.kind == InternalKernelArgType::ConstantBuffer)
+        {
+            self.constant_buffer(dev)?;
+        }
+
+        Ok(())
+    }
+
     // the painful part is, that host threads are allowed to modify the kernel object once it was
     // enqueued, so return a closure with all req data included.
     pub fn launch(
@@ -771,8 +1040,7 @@ impl Kernel {
         offsets: &[usize],
     ) -> CLResult<EventSig> {
         let nir = self.nirs.get(&q.device).unwrap();
-        let mut block = create_kernel_arr::<u32>(block, 1);
-        let mut grid = create_kernel_arr::<u32>(grid, 1);
+        let (block, grid) = launch_geometry(&q.device, self, grid, block)?;
         let offsets = create_kernel_arr::<u64>(offsets, 0);
         let mut input: Vec<u8> = Vec::new();
         let mut resource_info = Vec::new();
@@ -786,9 +1054,7 @@ impl Kernel {
         let mut img_formats: Vec<u16> = Vec::new();
         let mut img_orders: Vec<u16> = Vec::new();
 
-        optimize_local_size(&q.device, &mut grid, &mut block);
-
-        for (arg, val) in self.args.iter().zip(&self.values) {
+        for (arg, val) in self.args.iter().zip(self.values.iter()) {
             if arg.dead {
                 continue;
             }
@@ -800,8 +1066,11 @@ impl Kernel {
             {
                 input.resize(arg.offset, 0);
             }
-            match val.borrow().as_ref().unwrap() {
+            match val.lock().unwrap().as_ref().unwrap() {
                 KernelArgValue::Constant(c) => input.extend_from_slice(c),
+                KernelArgValue::SvmPointer(ptr) => {
+                    input.extend_from_slice(&ptr_bytes(*ptr, arg.size))
+                }
                 KernelArgValue::MemObject(mem) => {
                     let res = mem.get_res_of_dev(&q.device)?;
                     if mem.is_buffer() {
@@ -809,14 +1078,15 @@ impl Kernel {
                         resource_info.push((Some(res.clone()), arg.offset));
                     } else {
                         let format = mem.image_format.to_pipe_format().unwrap();
+                        let buf_range = mem.buffer_view_range();
                         let (formats, orders) = if arg.kind == KernelArgType::Image {
-                            iviews.push(res.pipe_image_view(format, false));
+                            iviews.push(res.pipe_image_view(format, false, buf_range));
                             (&mut img_formats, &mut img_orders)
                         } else if arg.kind == KernelArgType::RWImage {
-                            iviews.push(res.pipe_image_view(format, true));
+                            iviews.push(res.pipe_image_view(format, true, buf_range));
                             (&mut img_formats, &mut img_orders)
                         } else {
-                            sviews.push((res.clone(), format));
+                            sviews.push((res.clone(), format, buf_range));
                             (&mut tex_formats, &mut tex_orders)
                         };
 
@@ -857,19 +1127,7 @@ impl Kernel {
             match arg.kind {
                 InternalKernelArgType::ConstantBuffer => {
                     input.extend_from_slice(&[0; 8]);
-                    let buf = nir.get_constant_buffer();
-                    let res = Arc::new(
-                        q.device
-                            .screen()
-                            .resource_create_buffer(buf.len() as u32, ResourceType::Normal)
-                            .unwrap(),
-                    );
-                    q.device
-                        .helper_ctx()
-                        .exec(|ctx| {
-                            ctx.buffer_subdata(&res, 0, buf.as_ptr().cast(), buf.len() as u32)
-                        })
-                        .wait();
+                    let res = self.constant_buffer(&q.device)?;
                     resource_info.push((Some(res), arg.offset));
                 }
                 InternalKernelArgType::GlobalWorkOffsets => {
@@ -880,7 +1138,7 @@ impl Kernel {
                         q.device
                             .screen
                             .resource_create_buffer(printf_size, ResourceType::Normal)
-                            .unwrap(),
+                            .ok_or(CL_OUT_OF_RESOURCES)?,
                     );
 
                     input.extend_from_slice(&[0; 8]);
@@ -912,7 +1170,7 @@ impl Kernel {
 
             let mut sviews: Vec<_> = sviews
                 .iter()
-                .map(|(s, f)| ctx.create_sampler_view(s, *f))
+                .map(|(s, f, r)| ctx.create_sampler_view(s, *f, *r))
                 .collect();
             let samplers: Vec<_> = samplers
                 .iter()
@@ -965,10 +1223,12 @@ impl Kernel {
                     .with_ctx(ctx);
                 let mut buf: &[u8] =
                     unsafe { slice::from_raw_parts(tx.ptr().cast(), printf_size as usize) };
-                let length = u32::from_ne_bytes(*extract(&mut buf));
+                let length = u32::from_ne_bytes(*extract(&mut buf)) as usize;
 
-                // update our slice to make sure we don't go out of bounds
-                buf = &buf[0..(length - 4) as usize];
+                // update our slice to make sure we don't go out of bounds, the length includes the
+                // 4 bytes of the header itself
+                let length = length.clamp(4, printf_size as usize);
+                buf = &buf[0..length - 4];
 
                 unsafe {
                     u_printf(
@@ -1047,14 +1307,33 @@ impl Kernel {
         &self.args[idx as usize].spirv.type_name
     }
 
+    pub fn has_build(&self, dev: &Arc<Device>) -> bool {
+        self.nirs.contains_key(dev)
+    }
+
     pub fn priv_mem_size(&self, dev: &Arc<Device>) -> cl_ulong {
         self.nirs.get(dev).unwrap().scratch_size() as cl_ulong
     }
 
-    pub fn local_mem_size(&self, dev: &Arc<Device>) -> cl_ulong {
-        // TODO include args
+    // local memory declared inside the kernel
+    pub fn static_local_mem_size(&self, dev: &Arc<Device>) -> cl_ulong {
         self.nirs.get(dev).unwrap().shared_size() as cl_ulong
     }
+
+    // local memory bound to __local pointer args, unset args count as 0
+    pub fn dynamic_local_mem_size(&self) -> cl_ulong {
+        self.values
+            .iter()
+            .filter_map(|v| match *v.lock().unwrap() {
+                Some(KernelArgValue::LocalMem(size)) => Some(size as cl_ulong),
+                _ => None,
+            })
+            .sum()
+    }
+
+    pub fn local_mem_size(&self, dev: &Arc<Device>) -> cl_ulong {
+        self.static_local_mem_size(dev) + self.dynamic_local_mem_size()
+    }
 }
 
 impl Clone for Kernel {
@@ -1066,8 +1345,15 @@ impl Clone for Kernel {
             args: self.args.clone(),
             values: self.values.clone(),
             work_group_size: self.work_group_size,
+            subgroup_size: self.subgroup_size,
             attributes_string: self.attributes_string.clone(),
+            svm_ptrs: Mutex::new(self.svm_ptrs.lock().unwrap().clone()),
+            fine_grain_system_svm: AtomicBool::new(
+                self.fine_grain_system_svm.load(Ordering::Relaxed),
+            ),
             internal_args: self.internal_args.clone(),
+            constant_bufs: Mutex::new(self.constant_bufs.lock().unwrap().clone()),
+            local_multiples: Mutex::new(self.local_multiples.lock().unwrap().clone()),
             nirs: self.nirs.clone(),
         }
     }
@@ -1079,3 +1365,168 @@ impl Drop for Kernel {
         self.prog.kernel_count.fetch_sub(1, Ordering::Relaxed);
     }
 }
+
+#[test]
+fn test_arg_values_concurrent_set() {
+    // the ICD hands out the same kernel to all threads through its raw handle
+    struct Shared(*const KernelArgValues);
+    unsafe impl Send for Shared {}
+    impl Shared {
+        fn get(&self) -> &KernelArgValues {
+            unsafe { &*self.0 }
+        }
+    }
+
+    let values = KernelArgValues::new(4);
+    let threads: Vec<_> = (0..8)
+        .map(|t| {
+            let shared = Shared(&values);
+            std::thread::spawn(move || {
+                for i in 0..1000 {
+                    shared
+                        .get()
+                        .set(i % 4, KernelArgValue::Constant(vec![t; 4]));
+                }
+            })
+        })
+        .collect();
+
+    for t in threads {
+        t.join().unwrap();
+    }
+
+    assert!(values.all_set());
+    for i in 0..4 {
+        assert!(matches!(*values.lock(i), Some(KernelArgValue::Constant(_))));
+    }
+}
+
+#[test]
+fn test_arg_values_clear() {
+    let values = KernelArgValues::new(2);
+    values.set(0, KernelArgValue::LocalMem(4));
+    values.set(1, KernelArgValue::None);
+    assert!(values.all_set());
+
+    // launches of a cleared kernel fail until all args got set again
+    values.clear();
+    assert!(!values.all_set());
+    assert!(values.lock(0).is_none());
+    assert!(values.lock(1).is_none());
+
+    values.set(0, KernelArgValue::LocalMem(4));
+    assert!(!values.all_set());
+    values.set(1, KernelArgValue::None);
+    assert!(values.all_set());
+}
+
+#[test]
+fn test_arg_values_all_set() {
+    // kernels without args can be launched right away
+    assert!(KernelArgValues::new(0).all_set());
+
+    let values = KernelArgValues::new(2);
+    assert!(!values.all_set());
+
+    // setting the same arg twice must not count twice
+    values.set(0, KernelArgValue::Constant(vec![0; 4]));
+    values.set(0, KernelArgValue::Constant(vec![1; 4]));
+    assert!(!values.all_set());
+
+    values.set(1, KernelArgValue::LocalMem(16));
+    assert!(values.all_set());
+}
+
+#[test]
+fn test_svm_pointer_args() {
+    let values = KernelArgValues::new(2);
+    values.set(0, KernelArgValue::LocalMem(4));
+    assert!(!values.has_svm_pointers());
+
+    values.set(1, KernelArgValue::SvmPointer(0x1234));
+    assert!(values.has_svm_pointers());
+
+    assert_eq!(ptr_bytes(0x1234, 4), 0x1234_u32.to_ne_bytes());
+    assert_eq!(ptr_bytes(0x1234, 8), 0x1234_u64.to_ne_bytes());
+
+    // binding something else to the arg stops it from using SVM
+    values.set(1, KernelArgValue::None);
+    assert!(!values.has_svm_pointers());
+}
+
+#[test]
+fn test_launch_geometry() {
+    let max_block = [256, 256, 64];
+
+    // the application picked the local size
+    assert_eq!(
+        geometry(256, &max_block, 32, &[0; 3], &[64, 4], &[16, 2]),
+        Ok(([16, 2, 1], [4, 2, 1]))
+    );
+
+    // the required work group size is used without a local size
+    assert_eq!(
+        geometry(256, &max_block, 32, &[16, 1, 1], &[64], &[0]),
+        Ok(([16, 1, 1], [4, 1, 1]))
+    );
+
+    // uniform globals get the biggest fitting block
+    assert_eq!(
+        geometry(256, &max_block, 32, &[0; 3], &[1024], &[0]),
+        Ok(([256, 1, 1], [4, 1, 1]))
+    );
+
+    // otherwise the block has to divide the global size
+    assert_eq!(
+        geometry(256, &max_block, 32, &[0; 3], &[1000], &[0]),
+        Ok(([8, 1, 1], [125, 1, 1]))
+    );
+
+    // zero sized launches are no-ops in the api layer, but must not fail here either
+    assert_eq!(
+        geometry(256, &max_block, 32, &[0; 3], &[0], &[0]),
+        Ok(([1; 3], [0; 3]))
+    );
+    assert_eq!(
+        geometry(256, &max_block, 32, &[0; 3], &[16, 0], &[4, 4]),
+        Ok(([1; 3], [0; 3]))
+    );
+
+    // the amount of work groups has to fit into 32 bits
+    #[cfg(target_pointer_width = "64")]
+    assert_eq!(
+        geometry(256, &max_block, 32, &[0; 3], &[1 << 40], &[1]),
+        Err(CL_OUT_OF_RESOURCES)
+    );
+}
+
+#[test]
+fn test_launch_geometry_prime_global() {
+    // nothing but 1 divides a prime, so each work group only gets a single work item if it has to
+    // be uniform
+    assert_eq!(
+        geometry(256, &[256, 256, 64], 32, &[0; 3], &[1021], &[0]),
+        Ok(([1, 1, 1], [1021, 1, 1]))
+    );
+
+    // other dimensions can still use bigger blocks
+    assert_eq!(
+        geometry(256, &[256, 256, 64], 32, &[0; 3], &[1021, 64], &[0, 0]),
+        Ok(([1, 64, 1], [1021, 1, 1]))
+    );
+}
+
+#[test]
+fn test_launch_geometry_local_multiple() {
+    // a block of a single work item doesn't fill a multiple of 32, so one dimension gets folded
+    assert_eq!(
+        geometry(256, &[256, 256, 64], 32, &[0; 3], &[3, 5], &[0, 0]),
+        Ok(([3, 1, 1], [1, 5, 1]))
+    );
+
+    // but it's fine if the kernel got told to use a multiple of 1
+    assert_eq!(
+        geometry(256, &[256, 256, 64], 1, &[0; 3], &[3, 5], &[0, 0]),
+        Ok(([1, 1, 1], [3, 5, 1]))
+    );
+}
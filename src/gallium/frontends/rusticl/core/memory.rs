@@ -297,12 +297,18 @@ impl Mem {
             ResourceType::Normal
         };
 
-        let buffer = context.create_buffer(
-            size,
-            host_ptr,
-            bit_check(flags, CL_MEM_COPY_HOST_PTR),
-            res_type,
-        )?;
+        context.reserve_mem(size)?;
+        let buffer = context
+            .create_buffer(
+                size,
+                host_ptr,
+                bit_check(flags, CL_MEM_COPY_HOST_PTR),
+                res_type,
+            )
+            .map_err(|err| {
+                context.release_mem(size);
+                err
+            })?;
 
         let host_ptr = if bit_check(flags, CL_MEM_USE_HOST_PTR) {
             host_ptr
@@ -310,7 +316,7 @@ impl Mem {
             ptr::null_mut()
         };
 
-        Ok(Arc::new(Self {
+        let mem = Arc::new(Self {
             base: CLObjectBase::new(),
             context: context,
             parent: None,
@@ -326,7 +332,9 @@ impl Mem {
             cbs: Mutex::new(Vec::new()),
             res: Some(buffer),
             maps: Mappings::new(),
-        }))
+        });
+        mem.context.add_debug_mem(&mem);
+        Ok(mem)
     }
 
     pub fn new_sub_buffer(
@@ -341,7 +349,7 @@ impl Mem {
             unsafe { parent.host_ptr.add(offset) }
         };
 
-        Arc::new(Self {
+        let mem = Arc::new(Self {
             base: CLObjectBase::new(),
             context: parent.context.clone(),
             parent: Some(parent),
@@ -357,7 +365,9 @@ impl Mem {
             cbs: Mutex::new(Vec::new()),
             res: None,
             maps: Mappings::new(),
-        })
+        });
+        mem.context.add_debug_mem(&mem);
+        mem
     }
 
     pub fn new_image(
@@ -391,31 +401,41 @@ impl Mem {
             ResourceType::Normal
         };
 
+        let size = image_desc.pixels() * image_format.pixel_size().unwrap() as usize;
         let texture = if parent.is_none() {
-            Some(context.create_texture(
-                &image_desc,
-                image_format,
-                host_ptr,
-                bit_check(flags, CL_MEM_COPY_HOST_PTR),
-                res_type,
-            )?)
+            context.reserve_mem(size)?;
+            Some(
+                context
+                    .create_texture(
+                        &image_desc,
+                        image_format,
+                        host_ptr,
+                        bit_check(flags, CL_MEM_COPY_HOST_PTR),
+                        res_type,
+                    )
+                    .map_err(|err| {
+                        context.release_mem(size);
+                        err
+                    })?,
+            )
         } else {
             None
         };
 
         let host_ptr = if bit_check(flags, CL_MEM_USE_HOST_PTR) {
-            host_ptr
+            // images created from another memory object share its host_ptr
+            parent.as_ref().map_or(host_ptr, |p| p.host_ptr)
         } else {
             ptr::null_mut()
         };
 
-        Ok(Arc::new(Self {
+        let mem = Arc::new(Self {
             base: CLObjectBase::new(),
             context: context,
             parent: parent,
             mem_type: mem_type,
             flags: flags,
-            size: image_desc.pixels() * image_format.pixel_size().unwrap() as usize,
+            size: size,
             offset: 0,
             host_ptr: host_ptr,
             image_format: *image_format,
@@ -425,7 +445,9 @@ impl Mem {
             cbs: Mutex::new(Vec::new()),
             res: texture,
             maps: Mappings::new(),
-        }))
+        });
+        mem.context.add_debug_mem(&mem);
+        Ok(mem)
     }
 
     pub fn is_buffer(&self) -> bool {
@@ -512,6 +534,18 @@ impl Mem {
     ) -> CLResult<PipeTransfer> {
         assert!(!self.is_buffer());
 
+        // images created from buffers are backed by the buffer's resource
+        if self.mem_type == CL_MEM_OBJECT_IMAGE1D_BUFFER {
+            let bpp = self.image_format.pixel_size().unwrap() as usize;
+            return self.parent.as_ref().unwrap().tx_raw(
+                q,
+                ctx,
+                bx.x as usize * bpp,
+                bx.width as usize * bpp,
+                rw,
+            );
+        }
+
         let r = self.get_res()?.get(&q.device).unwrap();
         Ok(ctx.texture_map(r, bx, rw, ResourceMapType::Normal))
     }
@@ -524,6 +558,10 @@ impl Mem {
     ) -> CLResult<(PipeTransfer, Option<PipeResource>)> {
         assert!(!self.is_buffer());
 
+        if self.mem_type == CL_MEM_OBJECT_IMAGE1D_BUFFER {
+            return self.parent.as_ref().unwrap().tx_raw_async(q, rw);
+        }
+
         let r = self.get_res()?.get(&q.device).unwrap();
         let ctx = q.device.helper_ctx();
 
@@ -572,12 +610,12 @@ impl Mem {
     }
 
     fn get_res(&self) -> CLResult<&HashMap<Arc<Device>, Arc<PipeResource>>> {
-        self.parent
-            .as_ref()
-            .map_or(self, |p| p.as_ref())
-            .res
-            .as_ref()
-            .ok_or(CL_OUT_OF_HOST_MEMORY)
+        // images can be created from sub buffers, so we might have to walk up more than once
+        if let Some(parent) = &self.parent {
+            parent.get_res()
+        } else {
+            self.res.as_ref().ok_or(CL_OUT_OF_HOST_MEMORY)
+        }
     }
 
     pub fn get_res_of_dev(&self, dev: &Arc<Device>) -> CLResult<&Arc<PipeResource>> {
@@ -587,12 +625,23 @@ impl Mem {
     fn to_parent<'a>(&'a self, offset: &mut usize) -> &'a Self {
         if let Some(parent) = &self.parent {
             offset.add_assign(self.offset);
-            parent
+            parent.to_parent(offset)
         } else {
             self
         }
     }
 
+    // images created from (sub) buffers only cover part of the buffer resource
+    pub fn buffer_view_range(&self) -> Option<(u32, u32)> {
+        if self.mem_type != CL_MEM_OBJECT_IMAGE1D_BUFFER {
+            return None;
+        }
+
+        let mut offset = 0;
+        self.to_parent(&mut offset);
+        Some((offset as u32, self.size as u32))
+    }
+
     fn has_user_shadow_buffer(&self, d: &Device) -> CLResult<bool> {
         let r = self.get_res()?.get(d).unwrap();
         Ok(!r.is_user && bit_check(self.flags, CL_MEM_USE_HOST_PTR))
@@ -647,12 +696,6 @@ impl Mem {
         mut dst_origin: CLVec<usize>,
         region: &CLVec<usize>,
     ) -> CLResult<()> {
-        let src = self.to_parent(&mut src_origin[0]);
-        let dst = dst.to_parent(&mut dst_origin[0]);
-
-        let src_res = src.get_res()?.get(&q.device).unwrap();
-        let dst_res = dst.get_res()?.get(&q.device).unwrap();
-
         if self.is_buffer() && !dst.is_buffer() || !self.is_buffer() && dst.is_buffer() {
             let tx_src;
             let tx_dst;
@@ -702,7 +745,45 @@ impl Mem {
                     bpp as u8,
                 )
             }
+        } else if self.mem_type == CL_MEM_OBJECT_IMAGE1D_BUFFER
+            || dst.mem_type == CL_MEM_OBJECT_IMAGE1D_BUFFER
+        {
+            // images created from buffers are backed by a buffer resource, which can't be copied
+            // to or from textures with resource_copy_region.
+            // TODO: use resource_copy_region if both are buffer resources
+            let bpp = self.image_format.pixel_size().unwrap() as usize;
+            let tx_src = self.tx_image(
+                q,
+                ctx,
+                &create_box(&src_origin, region, self.mem_type)?,
+                RWFlags::RD,
+            )?;
+            let tx_dst = dst.tx_image(
+                q,
+                ctx,
+                &create_box(&dst_origin, region, dst.mem_type)?,
+                RWFlags::WR,
+            )?;
+
+            sw_copy(
+                tx_src.ptr(),
+                tx_dst.ptr(),
+                region,
+                &CLVec::default(),
+                tx_src.row_pitch() as usize,
+                tx_src.slice_pitch() as usize,
+                &CLVec::default(),
+                tx_dst.row_pitch() as usize,
+                tx_dst.slice_pitch() as usize,
+                bpp as u8,
+            )
         } else {
+            let src = self.to_parent(&mut src_origin[0]);
+            let dst = dst.to_parent(&mut dst_origin[0]);
+
+            let src_res = src.get_res()?.get(&q.device).unwrap();
+            let dst_res = dst.get_res()?.get(&q.device).unwrap();
+
             let bx = create_box(&src_origin, region, self.mem_type)?;
             let mut dst_origin: [u32; 3] = dst_origin.try_into()?;
 
@@ -746,8 +827,6 @@ impl Mem {
     ) -> CLResult<()> {
         assert!(!self.is_buffer());
 
-        let res = self.get_res()?.get(&q.device).unwrap();
-        let bx = create_box(origin, region, self.mem_type)?;
         // make sure we allocate multiples of 4 bytes so drivers don't read out of bounds or
         // unaligned.
         // TODO: use div_ceil once it's available
@@ -775,6 +854,25 @@ impl Mem {
             );
         }
 
+        if self.mem_type == CL_MEM_OBJECT_IMAGE1D_BUFFER {
+            let bpp = self.image_format.pixel_size().unwrap() as usize;
+            let pattern: Vec<u8> = new_pattern
+                .iter()
+                .flat_map(|v| v.to_ne_bytes())
+                .take(bpp)
+                .collect();
+
+            return self.parent.as_ref().unwrap().fill(
+                q,
+                ctx,
+                &pattern,
+                origin[0] * bpp,
+                region[0] * bpp,
+            );
+        }
+
+        let res = self.get_res()?.get(&q.device).unwrap();
+        let bx = create_box(origin, region, self.mem_type)?;
         ctx.clear_texture(res, &new_pattern, &bx);
 
         Ok(())
@@ -815,6 +913,17 @@ impl Mem {
             assert!(dst_slice_pitch == self.image_desc.image_slice_pitch);
             assert!(src_origin == &CLVec::default());
 
+            if self.mem_type == CL_MEM_OBJECT_IMAGE1D_BUFFER {
+                let bpp = self.image_format.pixel_size().unwrap() as usize;
+                return self.parent.as_ref().unwrap().write_from_user(
+                    q,
+                    ctx,
+                    dst_origin[0] * bpp,
+                    src,
+                    region[0] * bpp,
+                );
+            }
+
             let res = self.get_res()?.get(&q.device).unwrap();
             let bx = create_box(dst_origin, region, self.mem_type)?;
 
@@ -983,8 +1092,22 @@ impl Mem {
             )
         } else {
             if let Some(shadow) = lock.tx.get(&q.device).and_then(|tx| tx.shadow.as_ref()) {
-                let res = self.get_res_of_dev(&q.device)?;
-                let bx = self.image_desc.bx()?;
+                let mut offset = 0;
+                let b = self.to_parent(&mut offset);
+                let res = b.get_res_of_dev(&q.device)?;
+
+                let bx = if b.is_buffer() {
+                    pipe_box {
+                        width: self.size as i32,
+                        height: 1,
+                        depth: 1,
+                        x: offset as i32,
+                        ..Default::default()
+                    }
+                } else {
+                    self.image_desc.bx()?
+                };
+
                 ctx.resource_copy_region(res, shadow, &[0, 0, 0], &bx);
             }
             Ok(())
@@ -1168,6 +1291,13 @@ impl Drop for Mem {
         for (d, tx) in self.maps.lock().unwrap().tx.drain() {
             d.helper_ctx().unmap(tx.tx);
         }
+
+        self.context.remove_debug_mem(self);
+
+        // only memory objects with their own resources account for memory
+        if self.res.is_some() {
+            self.context.release_mem(self.size);
+        }
     }
 }
 
@@ -1270,3 +1400,23 @@ impl Sampler {
         ))
     }
 }
+
+#[test]
+fn test_sub_buffer_host_ptr() {
+    let ctx = Context::new(Vec::new(), Default::default());
+    let mut host = [0u8; 64];
+    let parent = Mem::new_buffer(
+        ctx,
+        CL_MEM_USE_HOST_PTR.into(),
+        host.len(),
+        host.as_mut_ptr().cast(),
+        Vec::new(),
+    )
+    .unwrap();
+    assert_eq!(parent.host_ptr, host.as_mut_ptr().cast());
+
+    // sub buffers alias the host memory at their offset
+    let sub = Mem::new_sub_buffer(parent.clone(), parent.flags, 16, 32);
+    assert_eq!(sub.host_ptr, unsafe { host.as_mut_ptr().add(16).cast() });
+    assert!(sub.has_same_parent(&parent));
+}
@@ -45,11 +45,20 @@ fn get_disk_cache() -> &'static Option<DiskCache> {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ProgramSourceType {
+    Source,
+    Il,
+    Binary,
+    Linked,
+}
+
 #[repr(C)]
 pub struct Program {
     pub base: CLObjectBase<CL_INVALID_PROGRAM>,
     pub context: Arc<Context>,
     pub devs: Vec<Arc<Device>>,
+    pub src_type: ProgramSourceType,
     pub src: CString,
     pub il: Vec<u8>,
     pub kernel_count: AtomicU32,
@@ -119,6 +128,15 @@ fn prepare_options(options: &str, dev: &Device) -> Vec<CString> {
         .collect()
 }
 
+// spirv_to_nir doesn't check whether the device supports everything a module uses, so kernels
+// using unsupported features would only fail at runtime. Reject what we know about at build time.
+fn check_spirv_support(spirv: &spirv::SPIRVBin, dev: &Device) -> Result<(), String> {
+    if spirv.has_capability(spirv::SPV_CAPABILITY_INT64_ATOMICS) && !dev.int64_atomics_supported() {
+        return Err(String::from("The device doesn't support 64 bit atomics\n"));
+    }
+    Ok(())
+}
+
 impl Program {
     pub fn new(context: &Arc<Context>, devs: &[Arc<Device>], src: CString) -> Arc<Program> {
         let builds = devs
@@ -141,6 +159,7 @@ impl Program {
             base: CLObjectBase::new(),
             context: context.clone(),
             devs: devs.to_vec(),
+            src_type: ProgramSourceType::Source,
             src: src,
             il: Vec::new(),
             kernel_count: AtomicU32::new(0),
@@ -214,6 +233,7 @@ impl Program {
             base: CLObjectBase::new(),
             context: context,
             devs: devs,
+            src_type: ProgramSourceType::Binary,
             src: CString::new("").unwrap(),
             il: Vec::new(),
             kernel_count: AtomicU32::new(0),
@@ -235,7 +255,7 @@ impl Program {
                 d.clone(),
                 ProgramDevBuild {
                     spirv: spirv,
-                    status: CL_BUILD_SUCCESS as cl_build_status,
+                    status: CL_BUILD_NONE,
                     log: String::from(""),
                     options: String::from(""),
                     bin_type: CL_PROGRAM_BINARY_TYPE_INTERMEDIATE,
@@ -247,6 +267,7 @@ impl Program {
             base: CLObjectBase::new(),
             devs: context.devs.clone(),
             context: context,
+            src_type: ProgramSourceType::Il,
             src: CString::new("").unwrap(),
             il: spirv.to_vec(),
             kernel_count: AtomicU32::new(0),
@@ -366,26 +387,33 @@ impl Program {
 
     pub fn build(&self, dev: &Arc<Device>, options: String) -> bool {
         // program binary
-        let is_il = !self.il.is_empty();
-        if self.src.as_bytes().is_empty() && !is_il {
+        if self.is_binary() {
             return true;
         }
+        let is_il = self.src_type == ProgramSourceType::Il;
 
-        let mut info = self.build_info();
-        let d = Self::dev_build_info(&mut info, dev);
         let lib = options.contains("-create-library");
         let args = prepare_options(&options, dev);
 
-        if !is_il {
-            let (spirv, log) = spirv::SPIRVBin::from_clc(
+        // Compiling the source is the expensive part, so don't hold the build lock while doing so.
+        // This allows builds for other devices and queries on this program to happen in parallel.
+        let compiled = if is_il {
+            None
+        } else {
+            Some(spirv::SPIRVBin::from_clc(
                 &self.src,
                 &args,
                 &Vec::new(),
                 get_disk_cache(),
                 dev.cl_features(),
                 dev.address_bits(),
-            );
+            ))
+        };
 
+        let mut info = self.build_info();
+        let d = Self::dev_build_info(&mut info, dev);
+
+        if let Some((spirv, log)) = compiled {
             d.log = log;
             if spirv.is_none() {
                 d.status = CL_BUILD_ERROR;
@@ -401,6 +429,14 @@ impl Program {
 
         d.log.push_str(&log);
         d.spirv = spirv;
+
+        if !lib {
+            if let Some(Err(err)) = d.spirv.as_ref().map(|s| check_spirv_support(s, dev)) {
+                d.log.push_str(&err);
+                d.spirv = None;
+            }
+        }
+
         if d.spirv.is_some() {
             d.bin_type = if lib {
                 CL_PROGRAM_BINARY_TYPE_LIBRARY
@@ -424,21 +460,24 @@ impl Program {
         headers: &[spirv::CLCHeader],
     ) -> bool {
         // program binary
-        let is_il = !self.il.is_empty();
-        if self.src.as_bytes().is_empty() && !is_il {
+        if self.is_binary() {
             return true;
         }
+        let is_il = self.src_type == ProgramSourceType::Il;
 
-        let mut info = self.build_info();
-        let d = Self::dev_build_info(&mut info, dev);
-
+        // there is nothing to compile for IL programs, they only need to be linked
         if is_il {
+            let mut info = self.build_info();
+            let d = Self::dev_build_info(&mut info, dev);
+            d.status = CL_BUILD_SUCCESS as cl_build_status;
+            d.options = options;
             d.bin_type = CL_PROGRAM_BINARY_TYPE_COMPILED_OBJECT;
             return true;
         }
 
         let args = prepare_options(&options, dev);
 
+        // see build
         let (spirv, log) = spirv::SPIRVBin::from_clc(
             &self.src,
             &args,
@@ -448,6 +487,9 @@ impl Program {
             dev.address_bits(),
         );
 
+        let mut info = self.build_info();
+        let d = Self::dev_build_info(&mut info, dev);
+
         d.spirv = spirv;
         d.log = log;
         d.options = options;
@@ -480,7 +522,14 @@ impl Program {
                 .map(|l| Self::dev_build_info(l, d).spirv.as_ref().unwrap())
                 .collect();
 
-            let (spirv, log) = spirv::SPIRVBin::link(&bins, lib);
+            let (mut spirv, mut log) = spirv::SPIRVBin::link(&bins, lib);
+
+            if !lib {
+                if let Some(Err(err)) = spirv.as_ref().map(|s| check_spirv_support(s, d)) {
+                    log.push_str(&err);
+                    spirv = None;
+                }
+            }
 
             let status;
             let bin_type;
@@ -515,6 +564,7 @@ impl Program {
             base: CLObjectBase::new(),
             context: context,
             devs: devs,
+            src_type: ProgramSourceType::Linked,
             src: CString::new("").unwrap(),
             il: Vec::new(),
             kernel_count: AtomicU32::new(0),
@@ -584,11 +634,87 @@ impl Program {
                 &d.lib_clc,
                 &mut [],
                 d.address_bits(),
+                d.int64_atomics_supported(),
             )
             .unwrap()
     }
 
     pub fn is_binary(&self) -> bool {
-        self.src.to_bytes().is_empty() && self.il.is_empty()
+        [ProgramSourceType::Binary, ProgramSourceType::Linked].contains(&self.src_type)
+    }
+}
+
+// creates a program for each source on the first device
+#[cfg(test)]
+fn test_programs(srcs: &[String]) -> Vec<Arc<Program>> {
+    let dev = Device::all()
+        .into_iter()
+        .next()
+        .expect("the program tests need a device");
+    let ctx = Context::new(vec![dev], Default::default());
+    srcs.iter()
+        .map(|src| Program::new(&ctx, &ctx.devs, CString::new(src.as_str()).unwrap()))
+        .collect()
+}
+
+// builds each program on its own thread and returns whether all builds succeeded
+#[cfg(test)]
+fn build_in_threads(progs: &[Arc<Program>]) -> bool {
+    // the ICD hands out the same program to all threads through its raw handle
+    struct Shared(*const Program);
+    unsafe impl Send for Shared {}
+    impl Shared {
+        fn get(&self) -> &Program {
+            unsafe { &*self.0 }
+        }
+    }
+
+    let threads: Vec<_> = progs
+        .iter()
+        .map(|p| {
+            let shared = Shared(Arc::as_ptr(p));
+            std::thread::spawn(move || {
+                let p = shared.get();
+                p.build(&p.devs[0], String::new())
+            })
+        })
+        .collect();
+
+    threads.into_iter().all(|t| t.join().unwrap())
+}
+
+#[test]
+#[ignore = "needs a device"]
+fn test_concurrent_builds_of_one_program() {
+    let progs = test_programs(&[String::from("kernel void k(global int *a) { a[0] = 1; }")]);
+    let p = &progs[0];
+
+    assert!(build_in_threads(&[p.clone(), p.clone()]));
+    assert_eq!(p.status(&p.devs[0]), CL_BUILD_SUCCESS as cl_build_status);
+    assert_eq!(p.kernels(), ["k"]);
+}
+
+// Not a real test, run with `--ignored --nocapture` to compare building programs one after the
+// other with building them in parallel.
+#[test]
+#[ignore = "benchmark, needs a device"]
+fn bench_parallel_builds() {
+    // different sources, so nothing comes out of the disk cache
+    let srcs = |val: &str| -> Vec<String> {
+        (0..16)
+            .map(|i| format!("kernel void k(global int *a) {{ a[{}] = {}; }}", i, val))
+            .collect()
+    };
+
+    let progs = test_programs(&srcs("1"));
+    let start = std::time::Instant::now();
+    for p in &progs {
+        assert!(p.build(&p.devs[0], String::new()));
     }
+    println!("sequential: {:?}", start.elapsed());
+
+    let progs = test_programs(&srcs("2"));
+    let start = std::time::Instant::now();
+    assert!(build_in_threads(&progs));
+    println!("parallel: {:?}", start.elapsed());
 }
@@ -2,8 +2,10 @@ use crate::api::icd::*;
 use crate::core::context::*;
 use crate::core::device::*;
 use crate::core::event::*;
+use crate::core::kernel::*;
 use crate::impl_cl_type_trait;
 
+use mesa_rust::pipe::context::PipeContext;
 use mesa_rust_util::properties::*;
 use rusticl_opencl_gen::*;
 
@@ -12,6 +14,8 @@ use std::sync::Arc;
 use std::sync::Mutex;
 use std::thread;
 use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
 
 #[repr(C)]
 pub struct Queue {
@@ -28,16 +32,52 @@ pub struct Queue {
 impl_cl_type_trait!(cl_command_queue, Queue, CL_INVALID_COMMAND_QUEUE);
 
 impl Queue {
+    /// Creates a queue submitting its commands on `device`. Out-of-order queues run independent
+    /// commands on `workers` threads with their own driver contexts, in-order queues always use a
+    /// single one.
     pub fn new(
         context: Arc<Context>,
         device: Arc<Device>,
         props: cl_command_queue_properties,
         props_v2: Option<Properties<cl_queue_properties>>,
+        workers: usize,
     ) -> CLResult<Arc<Queue>> {
+        // Commands of in-order queues have to be submitted one after the other on the same
+        // context, but out-of-order queues may run independent commands on a pool of workers.
+        let workers =
+            if props & CL_QUEUE_OUT_OF_ORDER_EXEC_MODE_ENABLE as cl_command_queue_properties != 0 {
+                workers
+            } else {
+                1
+            };
+
         // we assume that memory allocation is the only possible failure. Any other failure reason
         // should be detected earlier (e.g.: checking for CAPs).
         let pipe = device.screen().create_context().unwrap();
         let (tx_q, rx_t) = mpsc::channel::<Vec<Arc<Event>>>();
+        let (tx_w, rx_w) = mpsc::channel::<Arc<Event>>();
+        let rx_w = Arc::new(Mutex::new(rx_w));
+
+        let mut worker_thrds = Vec::new();
+        if workers > 1 {
+            for _ in 0..workers {
+                let pipe = device.screen().create_context().unwrap();
+                let rx_w = rx_w.clone();
+                worker_thrds.push(
+                    thread::Builder::new()
+                        .name("rusticl queue worker".into())
+                        .spawn(move || loop {
+                            let r = rx_w.lock().unwrap().recv();
+                            if r.is_err() {
+                                break;
+                            }
+                            Self::run_event(&r.unwrap(), &pipe);
+                        })
+                        .unwrap(),
+                );
+            }
+        }
+
         Ok(Arc::new(Self {
             base: CLObjectBase::new(),
             context: context,
@@ -54,15 +94,20 @@ impl Queue {
                             break;
                         }
                         let new_events = r.unwrap();
-                        for e in &new_events {
-                            // all events should be processed, but we might have to wait on user
-                            // events to happen
-                            let err = e.deps.iter().map(|e| e.wait()).find(|s| *s < 0);
-                            if let Some(err) = err {
-                                // if a dependency failed, fail this event as well
-                                e.set_user_status(err);
+                        for (i, e) in new_events.iter().enumerate() {
+                            if worker_thrds.is_empty() {
+                                Self::run_event(e, &pipe);
+                            } else if [CL_COMMAND_BARRIER, CL_COMMAND_MARKER].contains(&e.cmd_type)
+                            {
+                                // barriers and markers have to wait on all previously enqueued
+                                // commands, events of older batches are already done
+                                for e in &new_events[..i] {
+                                    e.wait();
+                                }
+                                Self::run_event(e, &pipe);
                             } else {
-                                e.call(&pipe);
+                                // the workers only go away after this thread stopped
+                                tx_w.send(e.clone()).unwrap();
                             }
                         }
                         for e in new_events {
@@ -75,20 +120,68 @@ impl Queue {
         }))
     }
 
+    fn run_event(e: &Event, pipe: &PipeContext) {
+        // all events should be processed, but we might have to wait on user events to happen
+        let err = e.deps.iter().map(|e| e.wait()).find(|s| *s < 0);
+        if let Some(err) = err {
+            // if a dependency failed, fail this event as well
+            e.set_failure(err, format!("a dependency failed with error {}", err));
+        } else {
+            e.call(pipe);
+        }
+    }
+
     pub fn queue(&self, e: Arc<Event>) {
         self.pending.lock().unwrap().push(e);
     }
 
+    /// Queues a launch of `kernel` and returns its event. The launch parameters are expected to be
+    /// validated already.
+    pub fn enqueue_ndrange(
+        self: &Arc<Self>,
+        kernel: &Arc<Kernel>,
+        cmd_type: cl_command_type,
+        work_dim: u32,
+        block: &[usize],
+        grid: &[usize],
+        offsets: &[usize],
+        deps: Vec<Arc<Event>>,
+    ) -> CLResult<Arc<Event>> {
+        let cb = kernel.launch(self, work_dim, block, grid, offsets)?;
+        let e = Event::new(self, cmd_type, deps, cb);
+        self.queue(e.clone());
+        Ok(e)
+    }
+
+    /// Queues `cb` to be run on the host once all `deps` completed. The returned event fails with
+    /// the error returned by `cb`, if any.
+    pub fn enqueue_host_callback(
+        self: &Arc<Self>,
+        deps: Vec<Arc<Event>>,
+        cb: Box<dyn Fn() -> CLResult<()>>,
+    ) -> Arc<Event> {
+        // host code run as part of the command stream is the closest thing to a native kernel
+        let e = Event::new(
+            self,
+            CL_COMMAND_NATIVE_KERNEL,
+            deps,
+            Box::new(move |_, _| cb()),
+        );
+        self.queue(e.clone());
+        e
+    }
+
     pub fn flush(&self, wait: bool) -> CLResult<()> {
         let mut p = self.pending.lock().unwrap();
-        let last = p.last().cloned();
+        let events: Vec<_> = (*p).drain(0..).collect();
         // This should never ever error, but if it does return an error
         self.chan_in
-            .send((*p).drain(0..).collect())
+            .send(events.clone())
             .map_err(|_| CL_OUT_OF_HOST_MEMORY)?;
         if wait {
-            if let Some(last) = last {
-                last.wait();
+            // with multiple workers the last event isn't necessarily the last one to complete
+            for e in events {
+                e.wait();
             }
         }
         Ok(())
@@ -105,3 +198,90 @@ impl Drop for Queue {
         let _ = self.flush(true);
     }
 }
+
+fn test_queue(workers: usize) -> Arc<Queue> {
+    let dev = Device::all()
+        .into_iter()
+        .next()
+        .expect("the queue tests need a device");
+    let ctx = Context::new(vec![dev.clone()], Default::default());
+    Queue::new(
+        ctx,
+        dev,
+        CL_QUEUE_OUT_OF_ORDER_EXEC_MODE_ENABLE.into(),
+        None,
+        workers,
+    )
+    .unwrap()
+}
+
+#[test]
+#[ignore = "needs a device"]
+fn test_barrier_waits_on_all_workers() {
+    let q = test_queue(4);
+
+    let order = Arc::new(Mutex::new(Vec::new()));
+    for i in 0..8 {
+        let order = order.clone();
+        q.enqueue_host_callback(
+            Vec::new(),
+            Box::new(move || {
+                // commands enqueued later finish first if they run concurrently
+                thread::sleep(Duration::from_millis(8 - i));
+                order.lock().unwrap().push(i);
+                Ok(())
+            }),
+        );
+    }
+
+    let barrier_order = order.clone();
+    q.queue(Event::new(
+        &q,
+        CL_COMMAND_BARRIER,
+        Vec::new(),
+        Box::new(move |_, _| {
+            barrier_order.lock().unwrap().push(u64::MAX);
+            Ok(())
+        }),
+    ));
+
+    let last_order = order.clone();
+    q.enqueue_host_callback(
+        Vec::new(),
+        Box::new(move || {
+            last_order.lock().unwrap().push(8);
+            Ok(())
+        }),
+    );
+
+    q.flush(true).unwrap();
+
+    // the barrier has to run after all commands before it and before all commands after it
+    let order = order.lock().unwrap();
+    assert_eq!(order.len(), 10);
+    assert!(order[..8].iter().all(|&i| i < 8));
+    assert_eq!(order[8], u64::MAX);
+    assert_eq!(order[9], 8);
+}
+
+// Not a real test, run with `--ignored --nocapture` to compare how long independent commands take
+// with a different number of workers.
+#[test]
+#[ignore = "benchmark, needs a device"]
+fn bench_independent_commands() {
+    for workers in [1, 2, 4] {
+        let q = test_queue(workers);
+        let start = Instant::now();
+        for _ in 0..64 {
+            q.enqueue_host_callback(
+                Vec::new(),
+                Box::new(|| {
+                    thread::sleep(Duration::from_millis(1));
+                    Ok(())
+                }),
+            );
+        }
+        q.flush(true).unwrap();
+        println!("{} workers: {:?}", workers, start.elapsed());
+    }
+}
@@ -1,6 +1,36 @@
 use mesa_rust_gen::*;
 use rusticl_opencl_gen::*;
 
+use std::env;
+use std::sync::OnceLock;
+
+// whether `flag` is part of `RUSTICL_DEBUG`, always false in release builds
+fn debug_flag(flag: &str) -> bool {
+    static FLAGS: OnceLock<Vec<String>> = OnceLock::new();
+
+    cfg!(debug_assertions)
+        && FLAGS
+            .get_or_init(|| {
+                env::var("RUSTICL_DEBUG").map_or(Vec::new(), |flags| {
+                    flags.split(',').map(String::from).collect()
+                })
+            })
+            .iter()
+            .any(|f| f == flag)
+}
+
+/// Whether the kernel argument audit got enabled through `RUSTICL_DEBUG=args`. Always false in
+/// release builds.
+pub fn debug_args() -> bool {
+    debug_flag("args")
+}
+
+/// Whether the reasons for rejected kernel launches should be printed, enabled through
+/// `RUSTICL_DEBUG=launch`. Always false in release builds.
+pub fn debug_launch() -> bool {
+    debug_flag("launch")
+}
+
 pub fn cl_mem_type_to_texture_target(mem_type: cl_mem_object_type) -> pipe_texture_target {
     match mem_type {
         CL_MEM_OBJECT_IMAGE1D => pipe_texture_target::PIPE_TEXTURE_1D,
@@ -8,7 +38,7 @@ pub fn cl_mem_type_to_texture_target(mem_type: cl_mem_object_type) -> pipe_textu
         CL_MEM_OBJECT_IMAGE3D => pipe_texture_target::PIPE_TEXTURE_3D,
         CL_MEM_OBJECT_IMAGE1D_ARRAY => pipe_texture_target::PIPE_TEXTURE_1D_ARRAY,
         CL_MEM_OBJECT_IMAGE2D_ARRAY => pipe_texture_target::PIPE_TEXTURE_2D_ARRAY,
-        CL_MEM_OBJECT_IMAGE1D_BUFFER => pipe_texture_target::PIPE_TEXTURE_1D,
+        CL_MEM_OBJECT_IMAGE1D_BUFFER => pipe_texture_target::PIPE_BUFFER,
         _ => pipe_texture_target::PIPE_TEXTURE_2D,
     }
 }
@@ -13,6 +13,9 @@ use std::ptr;
 use std::slice;
 
 const INPUT_STR: *const c_char = b"input.cl\0" as *const u8 as *const c_char;
+const SPV_MAGIC_NUMBER: u32 = 0x07230203;
+const SPV_OP_CAPABILITY: u32 = 17;
+pub const SPV_CAPABILITY_INT64_ATOMICS: u32 = 12;
 
 pub enum SpecConstant {
     None,
@@ -66,6 +69,20 @@ impl SPIRVBin {
                     key.extend_from_slice(h.source.as_bytes());
                 });
 
+                // the SPIR-V also depends on the device we compile for, so we must not pick up
+                // binaries compiled for devices with a different set of features.
+                key.extend_from_slice(&address_bits.to_ne_bytes());
+                key.extend_from_slice(&[
+                    features.fp16.into(),
+                    features.fp64.into(),
+                    features.int64.into(),
+                    features.images.into(),
+                    features.images_read_write.into(),
+                    features.images_write_3d.into(),
+                    features.intel_subgroups.into(),
+                    features.subgroups.into(),
+                ]);
+
                 let mut key = cache.gen_key(&key);
                 if let Some(data) = cache.get(&mut key) {
                     return (Some(Self::from_bin(&data)), String::from(""));
@@ -256,6 +273,7 @@ impl SPIRVBin {
         library: bool,
         clc_shader: *const nir_shader,
         address_bits: u32,
+        int64_atomics: bool,
     ) -> spirv_to_nir_options {
         let global_addr_format;
         let offset_addr_format;
@@ -282,6 +300,7 @@ impl SPIRVBin {
                 int8: true,
                 int16: true,
                 int64: true,
+                int64_atomics: int64_atomics,
                 kernel: true,
                 kernel_image: true,
                 kernel_image_read_write: true,
@@ -309,9 +328,11 @@ impl SPIRVBin {
         libclc: &NirShader,
         spec_constants: &mut [nir_spirv_specialization],
         address_bits: u32,
+        int64_atomics: bool,
     ) -> Option<NirShader> {
         let c_entry = CString::new(entry_point.as_bytes()).unwrap();
-        let spirv_options = Self::get_spirv_options(false, libclc.get_nir(), address_bits);
+        let spirv_options =
+            Self::get_spirv_options(false, libclc.get_nir(), address_bits, int64_atomics);
 
         let nir = unsafe {
             spirv_to_nir(
@@ -332,7 +353,7 @@ impl SPIRVBin {
     pub fn get_lib_clc(screen: &PipeScreen) -> Option<NirShader> {
         let nir_options = screen.nir_shader_compiler_options(pipe_shader_type::PIPE_SHADER_COMPUTE);
         let address_bits = screen.compute_param(pipe_compute_cap::PIPE_COMPUTE_CAP_ADDRESS_BITS);
-        let spirv_options = Self::get_spirv_options(true, ptr::null(), address_bits);
+        let spirv_options = Self::get_spirv_options(true, ptr::null(), address_bits, false);
         let shader_cache = DiskCacheBorrowed::as_ptr(&screen.shader_cache());
 
         NirShader::new(unsafe {
@@ -340,6 +361,54 @@ impl SPIRVBin {
         })
     }
 
+    pub fn has_capability(&self, cap: u32) -> bool {
+        let words: &[u32] =
+            unsafe { slice::from_raw_parts(self.spirv.data.cast(), self.spirv.size / 4) };
+
+        // all OpCapability instructions come right after the 5 word header
+        let mut i = 5;
+        while i < words.len() {
+            let opcode = words[i] & 0xffff;
+            let len = (words[i] >> 16) as usize;
+            if opcode != SPV_OP_CAPABILITY || len == 0 {
+                break;
+            }
+
+            if words.get(i + 1) == Some(&cap) {
+                return true;
+            }
+            i += len;
+        }
+        false
+    }
+
+    // checks the 5 word header every SPIR-V module starts with and returns the module in the byte
+    // order of the host, as modules may use either one
+    pub fn validate_header(bin: &[u8]) -> Option<Vec<u8>> {
+        if bin.len() < 5 * 4 || bin.len() % 4 != 0 {
+            return None;
+        }
+
+        let words: Vec<u32> = bin
+            .chunks_exact(4)
+            .map(|w| u32::from_ne_bytes([w[0], w[1], w[2], w[3]]))
+            .collect();
+        let words = if words[0] == SPV_MAGIC_NUMBER.swap_bytes() {
+            words.into_iter().map(u32::swap_bytes).collect()
+        } else {
+            words
+        };
+        let version = words[1];
+
+        // the version is encoded as 0 | major | minor | 0 and the schema has to be 0
+        let valid = words[0] == SPV_MAGIC_NUMBER
+            && version & 0xff0000ff == 0
+            && version >> 16 == 1
+            && words[4] == 0;
+
+        valid.then(|| words.into_iter().flat_map(u32::to_ne_bytes).collect())
+    }
+
     pub fn to_bin(&self) -> &[u8] {
         unsafe { slice::from_raw_parts(self.spirv.data.cast(), self.spirv.size) }
     }
@@ -429,3 +498,35 @@ impl SPIRVKernelArg {
         })
     }
 }
+
+#[test]
+fn test_validate_header() {
+    let to_bytes =
+        |words: &[u32]| -> Vec<u8> { words.iter().flat_map(|w| w.to_ne_bytes()).collect() };
+
+    // magic, SPIR-V 1.2, generator, bound, schema and a single OpCapability Kernel
+    let module = [SPV_MAGIC_NUMBER, 0x00010200, 0, 1, 0, 0x00020011, 6];
+    let bytes = to_bytes(&module);
+
+    assert_eq!(SPIRVBin::validate_header(&bytes), Some(bytes.clone()));
+    // modules in the other byte order get converted
+    let swapped = module.map(u32::swap_bytes);
+    assert_eq!(
+        SPIRVBin::validate_header(&to_bytes(&swapped)),
+        Some(bytes.clone())
+    );
+
+    // too short or not made of words
+    assert_eq!(SPIRVBin::validate_header(&bytes[..16]), None);
+    assert_eq!(SPIRVBin::validate_header(&bytes[..21]), None);
+
+    // unsupported version
+    let mut invalid = module;
+    invalid[1] = 0x00020000;
+    assert_eq!(SPIRVBin::validate_header(&to_bytes(&invalid)), None);
+
+    // non zero schema
+    let mut invalid = module;
+    invalid[4] = 1;
+    assert_eq!(SPIRVBin::validate_header(&to_bytes(&invalid)), None);
+}
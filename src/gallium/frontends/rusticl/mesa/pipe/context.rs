@@ -357,8 +357,9 @@ impl PipeContext {
         &self,
         res: &PipeResource,
         format: pipe_format,
+        buf_range: Option<(u32, u32)>,
     ) -> *mut pipe_sampler_view {
-        let template = res.pipe_sampler_view_template(format);
+        let template = res.pipe_sampler_view_template(format, buf_range);
         unsafe {
             self.pipe.as_ref().create_sampler_view.unwrap()(
                 self.pipe.as_ptr(),
@@ -43,12 +43,19 @@ impl PipeResource {
         unsafe { self.pipe.as_ref().unwrap().array_size }
     }
 
-    pub fn pipe_image_view(&self, format: pipe_format, read_write: bool) -> pipe_image_view {
+    // buf_range is the (offset, size) of buffer views, the whole resource if None
+    pub fn pipe_image_view(
+        &self,
+        format: pipe_format,
+        read_write: bool,
+        buf_range: Option<(u32, u32)>,
+    ) -> pipe_image_view {
         let u = if self.as_ref().target() == pipe_texture_target::PIPE_BUFFER {
+            let (offset, size) = buf_range.unwrap_or((0, self.as_ref().width0));
             pipe_image_view__bindgen_ty_1 {
                 buf: pipe_image_view__bindgen_ty_1__bindgen_ty_2 {
-                    offset: 0,
-                    size: self.as_ref().width0,
+                    offset: offset,
+                    size: size,
                 },
             }
         } else {
@@ -81,14 +88,20 @@ impl PipeResource {
         }
     }
 
-    pub fn pipe_sampler_view_template(&self, format: pipe_format) -> pipe_sampler_view {
+    pub fn pipe_sampler_view_template(
+        &self,
+        format: pipe_format,
+        buf_range: Option<(u32, u32)>,
+    ) -> pipe_sampler_view {
         let mut res = pipe_sampler_view::default();
         unsafe {
             u_sampler_view_default_template(&mut res, self.pipe, format);
         }
 
         if res.target() == pipe_texture_target::PIPE_BUFFER {
-            res.u.buf.size = self.as_ref().width0;
+            let (offset, size) = buf_range.unwrap_or((0, self.as_ref().width0));
+            res.u.buf.offset = offset;
+            res.u.buf.size = size;
         }
 
         res
@@ -237,6 +237,14 @@ impl PipeScreen {
         unsafe { *self.ldev.ldev }.type_
     }
 
+    pub fn get_timestamp(&self) -> u64 {
+        // not all drivers can read the GPU clock, so fall back to the CPU one
+        match unsafe { (*self.screen).get_timestamp } {
+            Some(get_timestamp) => unsafe { get_timestamp(self.screen) },
+            None => unsafe { os_time_get_nano() as u64 },
+        }
+    }
+
     pub fn is_format_supported(
         &self,
         format: pipe_format,
@@ -16,6 +16,7 @@
 
 #include "util/blob.h"
 #include "util/disk_cache.h"
+#include "util/os_time.h"
 #include "util/u_printf.h"
 #include "util/u_sampler.h"
 
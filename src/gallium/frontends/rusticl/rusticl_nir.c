@@ -54,7 +54,9 @@ rusticl_lower_intrinsics_instr(
         return val;
     }
     case nir_intrinsic_load_base_global_invocation_id:
-        return nir_load_var(b, state->base_global_invoc_id);
+        // the offsets are always passed in as 64 bit values
+        return nir_u2u(b, nir_load_var(b, state->base_global_invoc_id),
+                       nir_dest_bit_size(intrins->dest));
     case nir_intrinsic_load_constant_base_ptr:
         return nir_load_var(b, state->const_buf);
     case nir_intrinsic_load_printf_buffer_address:
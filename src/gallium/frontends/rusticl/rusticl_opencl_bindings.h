@@ -1,4 +1,5 @@
 #include <CL/cl_icd.h>
+#include <CL/cl_ext_intel.h>
 
 #define DECL_CL_STRUCT(name) struct name { const cl_icd_dispatch *dispatch; }
 DECL_CL_STRUCT(_cl_command_queue);